@@ -1,31 +1,44 @@
-use proc_macro2::{Span, TokenStream};
-use proc_macro_error::abort;
+use proc_macro2::TokenStream;
 use quote::{format_ident, quote, ToTokens};
-use syn::{parse::ParseStream, punctuated::Punctuated, Attribute, Ident, LitStr, Token};
+use syn::{
+    parenthesized, parse::ParseStream, Attribute, Field, Ident, LitInt, LitStr, Token, Type,
+};
 
-use std::collections::HashSet;
+use std::fmt;
 
 #[derive(Clone)]
 pub(crate) enum AttributeImpl {
     Input(Input),
     Output(Output),
     Connection(Connection),
+    Param(Param),
+    Submodule(Submodule),
+    Unconnected(Ident),
+}
+
+impl AttributeImpl {
+    /// Marks an input or output that is deliberately left unconnected inside the module.
+    pub(crate) const UNCONNECTED_KEY: &'static str = "unconnected";
 }
 
 pub(crate) struct AttributeImplBuilder {
     attribute: Option<Attribute>,
     field_ident: Option<Ident>,
+    field_ty: Type,
+    is_array: bool,
 }
 
 impl AttributeImplBuilder {
-    pub(crate) fn new(attribute: &Attribute, field_ident: &Option<Ident>) -> Option<Self> {
+    pub(crate) fn new(attribute: &Attribute, field: &Field) -> Option<Self> {
         if !attribute.path.is_ident("synth_module") {
             return None;
         }
 
         Some(Self {
             attribute: Some(attribute.clone()),
-            field_ident: field_ident.clone(),
+            field_ident: field.ident.clone(),
+            field_ty: field.ty.clone(),
+            is_array: matches!(field.ty, Type::Array(_)),
         })
     }
 
@@ -33,31 +46,58 @@ impl AttributeImplBuilder {
         let attribute = self.attribute.take().unwrap();
         let field_ident = self.field_ident.take();
 
+        let field_ident = match field_ident {
+            Some(field_ident) => field_ident,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &attribute,
+                    "attribute can only be used with named fields",
+                ))
+            }
+        };
+
+        let is_array = self.is_array;
+        let field_ty = self.field_ty;
+
         attribute.parse_args_with(|parse_input: ParseStream| {
             let ident = parse_input.parse::<Ident>()?;
 
-            let field_ident = match field_ident {
-                Some(field_ident) => field_ident,
-                None => abort!(
-                    Span::call_site(),
-                    "attribute can only be used with named fields"
-                ),
-            };
-
             let parsed = match ident.to_string().as_str() {
-                Input::KEY => AttributeImpl::Input(Input { field_ident }),
-                Output::KEY => AttributeImpl::Output(Output { field_ident }),
+                Input::KEY => AttributeImpl::Input(Input {
+                    field_ident,
+                    is_array,
+                }),
+                Output::KEY => AttributeImpl::Output(Output {
+                    field_ident,
+                    is_array,
+                }),
                 Connection::KEY => {
                     parse_input.parse::<Token![=]>()?;
                     AttributeImpl::Connection(Connection::parse_with_src(parse_input, field_ident)?)
                 }
+                Param::KEY if is_array => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "params can't be used on arrays of ports",
+                    ))
+                }
+                Param::KEY => AttributeImpl::Param(Param::parse_with_field(parse_input, field_ident)?),
+                Submodule::KEY if is_array => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "submodules can't be arrays",
+                    ))
+                }
+                Submodule::KEY => AttributeImpl::Submodule(Submodule::parse_with_field(parse_input, field_ident, field_ty)?),
+                AttributeImpl::UNCONNECTED_KEY => AttributeImpl::Unconnected(field_ident),
                 other => {
-                    let msg = format!("invalid attribute \"{}\"", other);
-                    abort!(
-                        field_ident,
-                        "{}", msg;
-                        help = "valid attributes are: \"input\", \"output\", \"connect\""
-                    );
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        format!(
+                            "invalid attribute \"{}\", valid attributes are: \"input\", \"output\", \"connect\", \"param\", \"submodule\", \"unconnected\"",
+                            other
+                        ),
+                    ))
                 }
             };
 
@@ -69,6 +109,7 @@ impl AttributeImplBuilder {
 #[derive(Clone)]
 pub(crate) struct Input {
     pub(crate) field_ident: Ident,
+    pub(crate) is_array: bool,
 }
 
 impl Input {
@@ -77,9 +118,9 @@ impl Input {
     const ACCESSOR_SUFFIX: &'static str = "_in";
 }
 
-impl ToString for Input {
-    fn to_string(&self) -> String {
-        self.field_ident.to_string()
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.field_ident.fmt(f)
     }
 }
 
@@ -88,9 +129,17 @@ impl ToTokens for Input {
         let field_name = format_ident!("{}", self.field_ident.to_string());
         let fn_name = format_ident!("{}{}", field_name, Self::ACCESSOR_SUFFIX);
 
-        let input_tokens = quote! {
-            pub fn #fn_name(&self) -> Option<NodeIndex<u32>> {
-                self.#field_name.index()
+        let input_tokens = if self.is_array {
+            quote! {
+                pub fn #fn_name(&self, index: usize) -> Option<NodeIndex<u32>> {
+                    self.#field_name.get(index).and_then(|port| port.index())
+                }
+            }
+        } else {
+            quote! {
+                pub fn #fn_name(&self) -> Option<NodeIndex<u32>> {
+                    self.#field_name.index()
+                }
             }
         };
 
@@ -101,6 +150,7 @@ impl ToTokens for Input {
 #[derive(Clone)]
 pub(crate) struct Output {
     pub(crate) field_ident: Ident,
+    pub(crate) is_array: bool,
 }
 
 impl Output {
@@ -109,9 +159,9 @@ impl Output {
     const ACCESSOR_SUFFIX: &'static str = "_out";
 }
 
-impl ToString for Output {
-    fn to_string(&self) -> String {
-        self.field_ident.to_string()
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.field_ident.fmt(f)
     }
 }
 
@@ -120,9 +170,17 @@ impl ToTokens for Output {
         let field_name = format_ident!("{}", self.field_ident.to_string());
         let fn_name = format_ident!("{}{}", field_name, Self::ACCESSOR_SUFFIX);
 
-        let output_tokens = quote! {
-            pub fn #fn_name(&self) -> Option<NodeIndex<u32>> {
-                self.#field_name.index()
+        let output_tokens = if self.is_array {
+            quote! {
+                pub fn #fn_name(&self, index: usize) -> Option<NodeIndex<u32>> {
+                    self.#field_name.get(index).and_then(|port| port.index())
+                }
+            }
+        } else {
+            quote! {
+                pub fn #fn_name(&self) -> Option<NodeIndex<u32>> {
+                    self.#field_name.index()
+                }
             }
         };
 
@@ -132,26 +190,288 @@ impl ToTokens for Output {
 
 #[derive(Clone)]
 pub(crate) struct Connection {
-    pub(crate) input_ident: Ident,
-    pub(crate) output_names: HashSet<LitStr>,
+    pub(crate) src_ident: Ident,
+    /// Output of a submodule field to connect from.
+    pub(crate) src_port: Option<LitStr>,
+    pub(crate) dst_names: Vec<LitStr>,
+    pub(crate) slot: Option<LitInt>,
 }
 
 impl Connection {
     pub(crate) const KEY: &'static str = "connect";
+
+    const SLOT_KEY: &'static str = "slot";
+    const FROM_KEY: &'static str = "from";
 }
 
 impl Connection {
+    /// Parses `"dst", "other_dst"`, followed by any of `slot = n` and `from = "port"`.
     pub(crate) fn parse_with_src(
         parse_input: ParseStream,
-        input_ident: Ident,
+        src_ident: Ident,
     ) -> syn::Result<Self> {
-        let output_names = HashSet::from_iter(
-            Punctuated::<LitStr, Token![,]>::parse_separated_nonempty(parse_input)?.into_iter(),
-        );
+        let mut dst_names = vec![parse_input.parse::<LitStr>()?];
+        let mut src_port = None;
+        let mut slot = None;
+
+        while parse_input.parse::<Option<Token![,]>>()?.is_some() {
+            if parse_input.peek(LitStr) {
+                dst_names.push(parse_input.parse::<LitStr>()?);
+                continue;
+            }
+
+            let key = parse_input.parse::<Ident>()?;
+
+            if key == Self::SLOT_KEY {
+                parse_input.parse::<Token![=]>()?;
+                slot = Some(parse_input.parse::<LitInt>()?);
+            } else if key == Self::FROM_KEY {
+                parse_input.parse::<Token![=]>()?;
+                src_port = Some(parse_input.parse::<LitStr>()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!(
+                        "expected a field name, \"{}\" or \"{}\"",
+                        Self::SLOT_KEY,
+                        Self::FROM_KEY
+                    ),
+                ));
+            }
+        }
 
         Ok(Self {
-            input_ident,
-            output_names,
+            src_ident,
+            src_port,
+            dst_names,
+            slot,
         })
     }
 }
+
+/// A field holding another `SynthModule`, built along with the module and connected through
+/// its ports as `"field.port"`.
+///
+/// With `submodule(controls)` the field must be a `ControlledModule`, and its controls are
+/// forwarded as a field of the module's own controls.
+#[derive(Clone)]
+pub(crate) struct Submodule {
+    pub(crate) field_ident: Ident,
+    /// Type of the field, present when its controls are forwarded.
+    pub(crate) controls: Option<Type>,
+}
+
+impl Submodule {
+    pub(crate) const KEY: &'static str = "submodule";
+
+    const CONTROLS_KEY: &'static str = "controls";
+
+    pub(crate) fn parse_with_field(
+        parse_input: ParseStream,
+        field_ident: Ident,
+        field_ty: Type,
+    ) -> syn::Result<Self> {
+        if parse_input.is_empty() {
+            return Ok(Self {
+                field_ident,
+                controls: None,
+            });
+        }
+
+        let content;
+        parenthesized!(content in parse_input);
+
+        let key = content.parse::<Ident>()?;
+
+        if key != Self::CONTROLS_KEY || !content.is_empty() {
+            return Err(syn::Error::new(
+                key.span(),
+                format!("expected \"{}\"", Self::CONTROLS_KEY),
+            ));
+        }
+
+        Ok(Self {
+            field_ident,
+            controls: Some(field_ty),
+        })
+    }
+
+    /// Local holding the forwarded controls while the graph is built.
+    pub(crate) fn controls_ident(&self) -> Ident {
+        format_ident!("{}_controls", self.field_ident)
+    }
+
+    /// Field of the generated controls struct, if the controls are forwarded.
+    pub(crate) fn control_field(&self) -> Option<TokenStream> {
+        let field_ident = &self.field_ident;
+        let ty = self.controls.as_ref()?;
+
+        Some(quote! {
+            pub #field_ident: <#ty as ::synth_module::ControlledModule>::Controls
+        })
+    }
+
+    /// Builds the submodule into `graph`, binding its controls if they are forwarded.
+    pub(crate) fn build(&self) -> TokenStream {
+        let field_ident = &self.field_ident;
+
+        if self.controls.is_some() {
+            let controls_ident = self.controls_ident();
+
+            quote! {
+                let (#field_ident, #controls_ident) =
+                    ::synth_module::ControlledModule::build_graph_with_controls(self.#field_ident, graph);
+                self.#field_ident = #field_ident;
+            }
+        } else {
+            quote! {
+                self.#field_ident = ::synth_module::SynthModule::build_graph(self.#field_ident, graph);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Submodule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.field_ident.fmt(f)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Param {
+    pub(crate) field_ident: Ident,
+    pub(crate) name: Ident,
+    unit: String,
+    min: f32,
+    max: f32,
+    default: Option<f32>,
+    smoothing: f32,
+}
+
+impl Param {
+    pub(crate) const KEY: &'static str = "param";
+
+    pub(crate) fn parse_with_field(parse_input: ParseStream, field_ident: Ident) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in parse_input);
+
+        let mut name = None;
+        let mut unit = String::new();
+        let mut range = None;
+        let mut default = None;
+        let mut smoothing = 0.0;
+
+        while !content.is_empty() {
+            let key = content.parse::<Ident>()?;
+            content.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "name" => {
+                    let lit = content.parse::<LitStr>()?;
+                    name = Some(lit.parse::<Ident>()?);
+                }
+                "unit" => unit = content.parse::<LitStr>()?.value(),
+                "range" => {
+                    let bounds;
+                    parenthesized!(bounds in content);
+
+                    let min = Self::parse_number(&bounds)?;
+                    bounds.parse::<Token![,]>()?;
+                    let max = Self::parse_number(&bounds)?;
+
+                    range = Some((min, max));
+                }
+                "default" => default = Some(Self::parse_number(&content)?),
+                "smoothing" => smoothing = Self::parse_number(&content)?,
+                other => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!(
+                            "invalid param key \"{}\", expected one of \"name\", \"range\", \"default\", \"unit\", \"smoothing\"",
+                            other
+                        ),
+                    ))
+                }
+            }
+
+            if !content.is_empty() {
+                content.parse::<Token![,]>()?;
+            }
+        }
+
+        let (min, max) = match range {
+            Some(range) => range,
+            None => {
+                return Err(syn::Error::new(
+                    field_ident.span(),
+                    "param requires a `range = (min, max)`",
+                ))
+            }
+        };
+
+        Ok(Self {
+            name: name.unwrap_or_else(|| field_ident.clone()),
+            field_ident,
+            unit,
+            min,
+            max,
+            default,
+            smoothing,
+        })
+    }
+
+    /// Parses a float or integer literal with an optional leading minus sign.
+    fn parse_number(parse_input: ParseStream) -> syn::Result<f32> {
+        let negative = parse_input.parse::<Option<Token![-]>>()?.is_some();
+
+        let value = if parse_input.peek(syn::LitInt) {
+            parse_input.parse::<syn::LitInt>()?.base10_parse::<f32>()?
+        } else {
+            parse_input.parse::<syn::LitFloat>()?.base10_parse::<f32>()?
+        };
+
+        Ok(if negative { -value } else { value })
+    }
+
+    /// Field of the generated controls struct.
+    pub(crate) fn control_field(&self) -> TokenStream {
+        let name = &self.name;
+
+        quote! {
+            pub #name: ::synth_module::port::ParamHandle
+        }
+    }
+
+    /// Local holding the handle while the graph is built.
+    pub(crate) fn handle_ident(&self) -> Ident {
+        format_ident!("{}_param", self.name)
+    }
+
+    /// Attaches a handle to the field's node, evaluating to the handle.
+    pub(crate) fn attach(&self) -> TokenStream {
+        let field_ident = &self.field_ident;
+        let name = self.name.to_string();
+        let unit = &self.unit;
+        let min = self.min;
+        let max = self.max;
+        let smoothing = self.smoothing;
+        let default = match self.default {
+            Some(default) => quote! { Some(#default) },
+            None => quote! { None },
+        };
+
+        quote! {
+            ::synth_module::port::ParamHandle::attach(
+                &mut self.#field_ident,
+                #name,
+                #unit,
+                ::synth_module::port::ParamSpec {
+                    min: #min,
+                    max: #max,
+                    default: #default,
+                    smoothing: #smoothing,
+                },
+            )
+        }
+    }
+}
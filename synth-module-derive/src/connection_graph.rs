@@ -1,103 +1,368 @@
-use crate::attributes::Connection;
+use crate::attributes::{Connection, Submodule};
 
-use petgraph::{graph::NodeIndex, visit::EdgeRef, Graph};
-use proc_macro2::TokenStream;
+use petgraph::{algo::toposort, graph::NodeIndex, visit::EdgeRef, Direction, Graph};
+use proc_macro2::{Span, TokenStream};
 use quote::{format_ident, quote};
+use syn::{Ident, LitStr};
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// One end of a connection: the node of a field, or a port of a submodule field.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Endpoint {
+    Field(String),
+    SubmoduleInput { field: String, port: Ident },
+    SubmoduleOutput { field: String, port: Ident },
+}
+
+impl Endpoint {
+    /// Parses a connection target, either `"field"` or `"submodule.port"`.
+    fn parse_dst(dst: &LitStr) -> syn::Result<Self> {
+        let value = dst.value();
+
+        match value.split_once('.') {
+            Some((field, port)) => Ok(Self::SubmoduleInput {
+                field: field.to_string(),
+                port: Self::parse_port(port, dst)?,
+            }),
+            None => Ok(Self::Field(value)),
+        }
+    }
+
+    fn parse_src(src: &Ident, port: Option<&LitStr>) -> syn::Result<Self> {
+        match port {
+            Some(port) => Ok(Self::SubmoduleOutput {
+                field: src.to_string(),
+                port: Self::parse_port(&port.value(), port)?,
+            }),
+            None => Ok(Self::Field(src.to_string())),
+        }
+    }
+
+    /// Spans the port on its literal, so a port the submodule doesn't have is reported there.
+    fn parse_port(port: &str, lit: &LitStr) -> syn::Result<Ident> {
+        syn::parse_str::<Ident>(port)
+            .map(|port| Ident::new(&port.to_string(), lit.span()))
+            .map_err(|_| syn::Error::new(lit.span(), format!("`{}` is not a valid port name", port)))
+    }
+
+    pub(crate) fn field(&self) -> &str {
+        match self {
+            Self::Field(field) => field,
+            Self::SubmoduleInput { field, .. } | Self::SubmoduleOutput { field, .. } => field,
+        }
+    }
+
+    fn is_array(&self, arrays: &HashSet<String>) -> bool {
+        matches!(self, Self::Field(field) if arrays.contains(field))
+    }
+
+    /// The node index of the endpoint, through the submodule's accessor for its ports.
+    fn index(&self) -> TokenStream {
+        let (field, accessor) = match self {
+            Self::Field(field) => {
+                let field = format_ident!("{}", field);
+                return quote! { self.#field.index().unwrap() };
+            }
+            Self::SubmoduleInput { field, port } => (field, Ident::new(&format!("{}_in", port), port.span())),
+            Self::SubmoduleOutput { field, port } => (field, Ident::new(&format!("{}_out", port), port.span())),
+        };
+
+        let field = format_ident!("{}", field);
+
+        quote! { self.#field.#accessor().unwrap() }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(field) => field.fmt(f),
+            Self::SubmoduleInput { field, port } | Self::SubmoduleOutput { field, port } => {
+                write!(f, "{}.{}", field, port)
+            }
+        }
+    }
+}
+
+/// A connection from one endpoint into another.
+pub(crate) struct Link {
+    /// Explicit position among the connections into the target, with the span of its literal.
+    slot: Option<(usize, Span)>,
+    /// Position of the connection in the struct, used to order connections without a slot.
+    order: usize,
+    span: Span,
+}
 
 pub(crate) struct ConnectionGraph {
-    pub(crate) fields_graph: Graph<String, ()>,
-    pub(crate) fields_to_nodes: HashMap<String, NodeIndex<u32>>,
+    pub(crate) endpoints_graph: Graph<Endpoint, Link>,
+    /// Endpoints grouped by field, in an order where every field comes after the fields
+    /// connected into it.
+    order: Vec<NodeIndex<u32>>,
 }
 
 impl ConnectionGraph {
-    pub(crate) fn new<'a>(connections: impl Iterator<Item = &'a Connection>) -> Self {
-        let mut fields_graph = Graph::<String, ()>::new();
-        let mut fields_to_nodes = HashMap::<String, NodeIndex<u32>>::new();
+    pub(crate) fn new<'a>(connections: impl Iterator<Item = &'a Connection>) -> syn::Result<Self> {
+        let mut endpoints_graph = Graph::<Endpoint, Link>::new();
+        let mut endpoints_to_nodes = HashMap::<Endpoint, NodeIndex<u32>>::new();
+
+        let mut node = |graph: &mut Graph<Endpoint, Link>, endpoint: Endpoint| {
+            *endpoints_to_nodes
+                .entry(endpoint.clone())
+                .or_insert_with(|| graph.add_node(endpoint))
+        };
+
+        let mut order = 0;
 
         for connection in connections {
-            let src = connection.input_ident.to_string();
+            let slot = match &connection.slot {
+                Some(slot) => Some((slot.base10_parse::<usize>()?, slot.span())),
+                None => None,
+            };
 
-            for dst in connection.output_names.iter().cloned() {
-                let dst = dst.value();
+            let src = Endpoint::parse_src(&connection.src_ident, connection.src_port.as_ref())?;
+            let src_idx = node(&mut endpoints_graph, src);
 
-                let src_idx = match fields_to_nodes.get(&src) {
-                    Some(src) => *src,
-                    None => {
-                        let idx = fields_graph.add_node(src.clone());
-                        fields_to_nodes.insert(src.clone(), idx);
-                        idx
-                    }
+            for dst in connection.dst_names.iter() {
+                let dst_idx = node(&mut endpoints_graph, Endpoint::parse_dst(dst)?);
+
+                let link = Link {
+                    slot,
+                    order,
+                    span: dst.span(),
                 };
 
-                let dst_idx = match fields_to_nodes.get(&dst) {
-                    Some(dst) => *dst,
-                    None => {
-                        let idx = fields_graph.add_node(dst.clone());
-                        fields_to_nodes.insert(dst, idx);
-                        idx
-                    }
+                endpoints_graph.add_edge(src_idx, dst_idx, link);
+                order += 1;
+            }
+        }
+
+        Self::validate_slots(&endpoints_graph)?;
+        let order = Self::topological_order(&endpoints_graph)?;
+
+        Ok(Self {
+            endpoints_graph,
+            order,
+        })
+    }
+
+    /// Every plain field that takes part in a connection.
+    pub(crate) fn fields(&self) -> impl Iterator<Item = &String> + '_ {
+        self.endpoints_graph
+            .node_indices()
+            .filter_map(move |idx| match &self.endpoints_graph[idx] {
+                Endpoint::Field(field) => Some(field),
+                _ => None,
+            })
+    }
+
+    /// Checks that no two connections into an endpoint share a slot, and that the slots leave no
+    /// gaps.
+    fn validate_slots(endpoints_graph: &Graph<Endpoint, Link>) -> syn::Result<()> {
+        for dst in endpoints_graph.node_indices() {
+            let mut links = endpoints_graph
+                .edges_directed(dst, Direction::Incoming)
+                .map(|edge| edge.weight())
+                .collect::<Vec<_>>();
+            links.sort_by_key(|link| link.order);
+
+            let mut taken = HashSet::new();
+
+            for link in links.iter() {
+                let (slot, span) = match link.slot {
+                    Some(slot) => slot,
+                    None => continue,
                 };
 
-                fields_graph.add_edge(src_idx, dst_idx, ());
+                if slot >= links.len() {
+                    return Err(syn::Error::new(
+                        span,
+                        format!(
+                            "slot {} is out of range, `{}` only has {} connection(s) into it",
+                            slot,
+                            endpoints_graph[dst],
+                            links.len()
+                        ),
+                    ));
+                }
+
+                if !taken.insert(slot) {
+                    return Err(syn::Error::new(
+                        span,
+                        format!("slot {} of `{}` is already taken", slot, endpoints_graph[dst]),
+                    ));
+                }
             }
         }
 
-        Self {
-            fields_graph,
-            fields_to_nodes,
+        Ok(())
+    }
+
+    /// Sorts the endpoints so every field comes after the fields connected into it, failing on
+    /// a cycle. A submodule counts as a single field, since any of its inputs may reach any of
+    /// its outputs.
+    fn topological_order(endpoints_graph: &Graph<Endpoint, Link>) -> syn::Result<Vec<NodeIndex<u32>>> {
+        let mut fields_graph = Graph::<&str, Span>::new();
+        let mut fields_to_nodes = HashMap::<&str, NodeIndex<u32>>::new();
+
+        for endpoint in endpoints_graph.node_indices() {
+            let field = endpoints_graph[endpoint].field();
+
+            fields_to_nodes
+                .entry(field)
+                .or_insert_with(|| fields_graph.add_node(field));
         }
+
+        for edge in endpoints_graph.edge_references() {
+            let src = fields_to_nodes[endpoints_graph[edge.source()].field()];
+            let dst = fields_to_nodes[endpoints_graph[edge.target()].field()];
+
+            fields_graph.add_edge(src, dst, edge.weight().span);
+        }
+
+        let fields = toposort(&fields_graph, None).map_err(|cycle| {
+            let field = cycle.node_id();
+
+            let span = fields_graph
+                .edges_directed(field, Direction::Incoming)
+                .map(|edge| *edge.weight())
+                .next()
+                .unwrap_or_else(Span::call_site);
+
+            syn::Error::new(
+                span,
+                format!("connections into `{}` form a cycle", fields_graph[field]),
+            )
+        })?;
+
+        Ok(fields
+            .into_iter()
+            .flat_map(|field| {
+                let field = fields_graph[field];
+
+                endpoints_graph
+                    .node_indices()
+                    .filter(move |&endpoint| endpoints_graph[endpoint].field() == field)
+            })
+            .collect())
     }
 
-    pub(crate) fn generate_node_additions(&self, inputs: &Vec<String>, outputs: &Vec<String>) -> TokenStream {
-        let mut tokens = TokenStream::new();
+    /// Connections into `dst`, ordered by slot. Connections without a slot fill the free slots
+    /// in the order they are declared.
+    fn ordered_sources(&self, dst: NodeIndex<u32>) -> Vec<NodeIndex<u32>> {
+        let mut links = self
+            .endpoints_graph
+            .edges_directed(dst, Direction::Incoming)
+            .collect::<Vec<_>>();
+        links.sort_by_key(|edge| edge.weight().order);
 
-        for input in inputs {
-            let src_ident = format_ident!("{}", input);
+        let mut slots = vec![None; links.len()];
 
-            tokens.extend(quote! {
-                self.#src_ident.connect(graph);
-            });
+        for edge in links.iter() {
+            if let Some((slot, _)) = edge.weight().slot {
+                slots[slot] = Some(edge.source());
+            }
+        }
 
-            for output in outputs {
-                let input_to_output = self
-                    .fields_graph
-                    .edges_connecting(self.fields_to_nodes[input], self.fields_to_nodes[output]);
+        let mut unslotted = links
+            .iter()
+            .filter(|edge| edge.weight().slot.is_none())
+            .map(|edge| edge.source());
 
-                for edge in input_to_output {
-                    let dst_ident = format_ident!("{}", self.fields_graph[edge.target()]);
+        slots
+            .into_iter()
+            .map(|slot| slot.or_else(|| unslotted.next()).unwrap())
+            .collect()
+    }
 
-                    tokens.extend(quote! {
-                        self.#dst_ident.connect(graph);
-                    });
-                }
+    /// Builds the submodules, then adds a node for every port and connected field.
+    pub(crate) fn generate_node_additions(
+        &self,
+        ports: &[String],
+        submodules: &[Submodule],
+        arrays: &HashSet<String>,
+    ) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        let mut added = HashSet::new();
+
+        for submodule in submodules {
+            tokens.extend(submodule.build());
+        }
+
+        for field in ports.iter().chain(self.fields()) {
+            if !added.insert(field) {
+                continue;
+            }
+
+            let ident = format_ident!("{}", field);
+
+            if arrays.contains(field) {
+                tokens.extend(quote! {
+                    for port in self.#ident.iter_mut() {
+                        port.connect(graph);
+                    }
+                });
+            } else {
+                tokens.extend(quote! {
+                    self.#ident.connect(graph);
+                });
             }
         }
 
         tokens
     }
 
-    pub(crate) fn generate_node_connections(&self, inputs: &Vec<String>, outputs: &Vec<String>) -> TokenStream {
+    /// Adds the edges into each endpoint in topological order.
+    ///
+    /// A node's inputs come out of the graph in the reverse of the order their edges were added,
+    /// so the edges into an endpoint are added from its last slot to its first, and arrays from
+    /// their last port to their first.
+    pub(crate) fn generate_node_connections(&self, arrays: &HashSet<String>) -> TokenStream {
         let mut tokens = TokenStream::new();
 
-        for input in inputs {
-            for output in outputs {
-                let input_to_output = self
-                    .fields_graph
-                    .edges_connecting(self.fields_to_nodes[input], self.fields_to_nodes[output]);
+        for &dst in self.order.iter() {
+            for src in self.ordered_sources(dst).into_iter().rev() {
+                let src = &self.endpoints_graph[src];
+                let dst = &self.endpoints_graph[dst];
 
-                for edge in input_to_output {
-                    let src_ident = format_ident!("{}", self.fields_graph[edge.source()]);
-                    let dst_ident = format_ident!("{}", self.fields_graph[edge.target()]);
-
-                    tokens.extend(quote! {
-                        graph.add_edge(self.#src_ident.index().unwrap(), self.#dst_ident.index().unwrap(), ());
-                    });
-                }
+                tokens.extend(Self::generate_edges(src, dst, arrays));
             }
         }
 
         tokens
     }
+
+    /// Connects every port of `src` to every port of `dst`, where either can be an array.
+    fn generate_edges(src: &Endpoint, dst: &Endpoint, arrays: &HashSet<String>) -> TokenStream {
+        let src_ident = format_ident!("{}", src.field());
+        let dst_ident = format_ident!("{}", dst.field());
+        let src_index = src.index();
+        let dst_index = dst.index();
+
+        match (src.is_array(arrays), dst.is_array(arrays)) {
+            (false, false) => quote! {
+                graph.add_edge(#src_index, #dst_index, ());
+            },
+            (true, false) => quote! {
+                for src in self.#src_ident.iter().rev() {
+                    graph.add_edge(src.index().unwrap(), #dst_index, ());
+                }
+            },
+            (false, true) => quote! {
+                for dst in self.#dst_ident.iter() {
+                    graph.add_edge(#src_index, dst.index().unwrap(), ());
+                }
+            },
+            (true, true) => quote! {
+                for dst in self.#dst_ident.iter() {
+                    for src in self.#src_ident.iter().rev() {
+                        graph.add_edge(src.index().unwrap(), dst.index().unwrap(), ());
+                    }
+                }
+            },
+        }
+    }
 }
@@ -11,12 +11,9 @@ impl FieldImpl {
         let attributes = field
             .attrs
             .iter()
-            .map(|attribute| {
-                AttributeImplBuilder::new(attribute, &field.ident)
-                    .and_then(|builder| builder.build().ok())
-            })
-            .filter_map(|opt| opt)
-            .collect();
+            .filter_map(|attribute| AttributeImplBuilder::new(attribute, field))
+            .map(AttributeImplBuilder::build)
+            .collect::<syn::Result<_>>()?;
 
         Ok(Self { attributes })
     }
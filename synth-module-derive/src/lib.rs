@@ -2,14 +2,19 @@ mod attributes;
 mod connection_graph;
 mod fields;
 
-use attributes::{AttributeImpl, Input, Output, Connection};
+use attributes::{AttributeImpl, Input, Output, Connection, Param, Submodule};
 use fields::FieldImpl;
 use connection_graph::ConnectionGraph;
 
 use proc_macro2::TokenStream;
-use proc_macro_error::{abort, proc_macro_error};
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, Attribute, DataStruct, DeriveInput, Ident};
+use proc_macro_error::proc_macro_error;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse_macro_input, Attribute, DataStruct, DeriveInput, GenericParam, Generics, Ident, LitStr, Type,
+    Visibility,
+};
+
+use std::collections::HashSet;
 
 
 #[proc_macro_error]
@@ -19,15 +24,20 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 
     match StructImpl::new(input) {
         Ok(impl_struct) => impl_struct.into_token_stream().into(),
-        Err(err) => abort!(err),
+        Err(err) => err.to_compile_error().into(),
     }
 }
 
 struct StructImpl {
     name: Ident,
+    vis: Visibility,
+    generics: Generics,
     inputs: Vec<Input>,
     outputs: Vec<Output>,
-    connections: Vec<Connection>,
+    submodules: Vec<Submodule>,
+    connection_graph: ConnectionGraph,
+    arrays: HashSet<String>,
+    params: Vec<Param>,
 }
 
 impl StructImpl {
@@ -37,20 +47,31 @@ impl StructImpl {
                 input.ident.span(),
                 "cannot derive `SynthModule` for enums or unions.",
             )),
-            syn::Data::Struct(data) => Self::impl_struct(input.ident, input.attrs, data),
+            syn::Data::Struct(data) => {
+                Self::impl_struct(input.ident, input.vis, input.generics, input.attrs, data)
+            }
         }
     }
 
-    fn impl_struct(name: Ident, _attrs: Vec<Attribute>, data: DataStruct) -> syn::Result<Self> {
+    fn impl_struct(
+        name: Ident,
+        vis: Visibility,
+        generics: Generics,
+        _attrs: Vec<Attribute>,
+        data: DataStruct,
+    ) -> syn::Result<Self> {
         let fields = data
             .fields
             .iter()
-            .filter_map(|field| FieldImpl::new(field).ok())
-            .collect::<Vec<_>>();
+            .map(FieldImpl::new)
+            .collect::<syn::Result<Vec<_>>>()?;
 
         let mut inputs = vec![];
         let mut outputs = vec![];
         let mut connections = vec![];
+        let mut params = vec![];
+        let mut submodules = vec![];
+        let mut unconnected = vec![];
 
         for field in fields {
             for attribute in field.attributes {
@@ -58,18 +79,169 @@ impl StructImpl {
                     AttributeImpl::Input(input) => inputs.push(input.clone()),
                     AttributeImpl::Output(output) => outputs.push(output.clone()),
                     AttributeImpl::Connection(connection) => connections.push(connection.clone()),
+                    AttributeImpl::Param(param) => params.push(param.clone()),
+                    AttributeImpl::Submodule(submodule) => submodules.push(submodule.clone()),
+                    AttributeImpl::Unconnected(field_ident) => unconnected.push(field_ident.clone()),
                 }
             }
         }
 
+        let field_names = data
+            .fields
+            .iter()
+            .filter_map(|field| field.ident.as_ref().map(ToString::to_string))
+            .collect::<HashSet<_>>();
+        let arrays = data
+            .fields
+            .iter()
+            .filter(|field| matches!(field.ty, Type::Array(_)))
+            .filter_map(|field| field.ident.as_ref().map(ToString::to_string))
+            .collect::<HashSet<_>>();
+
+        let submodule_names = submodules.iter().map(ToString::to_string).collect::<HashSet<_>>();
+
+        Self::validate_connections(&connections, &field_names, &submodule_names)?;
+        Self::validate_ports(&inputs, &outputs, &connections, &unconnected)?;
+        let connection_graph = ConnectionGraph::new(connections.iter())?;
+
         Ok(Self {
             name,
+            vis,
+            generics,
             inputs,
             outputs,
-            connections,
+            submodules,
+            connection_graph,
+            arrays,
+            params,
         })
     }
 
+    /// Checks that every `connect` runs to an existing field or submodule input, at most once and
+    /// never back to itself, reporting every problem on the offending attribute.
+    fn validate_connections(
+        connections: &[Connection],
+        field_names: &HashSet<String>,
+        submodule_names: &HashSet<String>,
+    ) -> syn::Result<()> {
+        let mut errors = Vec::new();
+        let mut connected = HashSet::new();
+
+        for connection in connections.iter() {
+            let src = connection.src_ident.to_string();
+
+            match (&connection.src_port, submodule_names.contains(&src)) {
+                (None, true) => errors.push(syn::Error::new(
+                    connection.src_ident.span(),
+                    format!("`{}` is a submodule, pick the output to connect with `from = \"port\"`", src),
+                )),
+                (Some(port), false) => errors.push(syn::Error::new(
+                    port.span(),
+                    format!("`from` can only be used on submodules, `{}` is not marked as one", src),
+                )),
+                _ => {}
+            }
+
+            let src_port = connection.src_port.as_ref().map(LitStr::value);
+
+            for dst in connection.dst_names.iter() {
+                let dst_name = dst.value();
+                let (dst_field, dst_port) = match dst_name.split_once('.') {
+                    Some((field, port)) => (field, Some(port)),
+                    None => (dst_name.as_str(), None),
+                };
+
+                let error = if dst_field == src {
+                    Some(format!("`{}` cannot be connected to itself", src))
+                } else if !field_names.contains(dst_field) {
+                    Some(format!("no field named `{}` to connect to", dst_field))
+                } else if dst_port.is_some() && !submodule_names.contains(dst_field) {
+                    Some(format!("`{}` is not marked as a submodule", dst_field))
+                } else if dst_port.is_none() && submodule_names.contains(dst_field) {
+                    Some(format!("`{}` is a submodule, connect to one of its inputs as \"{}.port\"", dst_field, dst_field))
+                } else if !connected.insert((src.clone(), src_port.clone(), dst_name.clone())) {
+                    Some(format!("`{}` is already connected to `{}`", src, dst_name))
+                } else {
+                    None
+                };
+
+                if let Some(error) = error {
+                    errors.push(syn::Error::new(dst.span(), error));
+                }
+            }
+        }
+
+        match errors.into_iter().reduce(|mut combined, error| {
+            combined.combine(error);
+            combined
+        }) {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects inputs that feed nothing and outputs that nothing feeds, unless they are marked
+    /// `#[synth_module(unconnected)]`, and rejects that mark on ports that are connected.
+    fn validate_ports(
+        inputs: &[Input],
+        outputs: &[Output],
+        connections: &[Connection],
+        unconnected: &[Ident],
+    ) -> syn::Result<()> {
+        let sources = connections
+            .iter()
+            .map(|connection| connection.src_ident.to_string())
+            .collect::<HashSet<_>>();
+        let targets = connections
+            .iter()
+            .flat_map(|connection| connection.dst_names.iter().map(|name| name.value()))
+            .collect::<HashSet<_>>();
+        let marked = unconnected.iter().map(ToString::to_string).collect::<HashSet<_>>();
+
+        let inputs = inputs
+            .iter()
+            .map(|input| (&input.field_ident, sources.contains(&input.to_string()), "input", "is not connected to anything"));
+        let outputs = outputs
+            .iter()
+            .map(|output| (&output.field_ident, targets.contains(&output.to_string()), "output", "has nothing connected to it"));
+
+        let mut errors = inputs
+            .chain(outputs)
+            .filter(|(field_ident, connected, _, _)| !connected && !marked.contains(&field_ident.to_string()))
+            .map(|(field_ident, _, kind, problem)| {
+                syn::Error::new(
+                    field_ident.span(),
+                    format!(
+                        "{} `{}` {}, connect it or mark it `#[synth_module({})]`",
+                        kind,
+                        field_ident,
+                        problem,
+                        AttributeImpl::UNCONNECTED_KEY
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for field_ident in unconnected {
+            let field = field_ident.to_string();
+
+            if sources.contains(&field) || targets.contains(&field) {
+                errors.push(syn::Error::new(
+                    field_ident.span(),
+                    format!("`{}` is connected, so it can't be marked `{}`", field, AttributeImpl::UNCONNECTED_KEY),
+                ));
+            }
+        }
+
+        match errors.into_iter().reduce(|mut combined, error| {
+            combined.combine(error);
+            combined
+        }) {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+
     fn input_field_names(&self) -> impl Iterator<Item = String> + '_ {
         self.inputs.iter().map(ToString::to_string)
     }
@@ -88,6 +260,104 @@ impl StructImpl {
         quote! { #(#inputs_accessors)* }
     }
 
+    /// Marks the type and lifetime parameters of the module as used by its controls struct,
+    /// since only the forwarded submodule controls might mention them.
+    fn generate_controls_marker(&self) -> Option<TokenStream> {
+        let params = self
+            .generics
+            .params
+            .iter()
+            .filter_map(|param| match param {
+                GenericParam::Type(param) => {
+                    let ident = &param.ident;
+                    Some(quote! { #ident })
+                }
+                GenericParam::Lifetime(param) => {
+                    let lifetime = &param.lifetime;
+                    Some(quote! { &#lifetime () })
+                }
+                GenericParam::Const(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        if params.is_empty() {
+            None
+        } else {
+            Some(quote! { ::std::marker::PhantomData<fn() -> (#(#params,)*)> })
+        }
+    }
+
+    /// Generates a `ControlledModule` impl that attaches the parameters before building the
+    /// graph, along with the struct holding their handles and the forwarded submodule controls.
+    /// The struct shares the module's visibility and generics.
+    fn generate_controlled_build_graph(
+        &self,
+        add_audio_graph_nodes: TokenStream,
+        connect_audio_graph_nodes: TokenStream,
+    ) -> TokenStream {
+        let name = &self.name;
+        let vis = &self.vis;
+        let controls_name = format_ident!("{}Controls", name);
+        let generics = &self.generics;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        let (marker_field, marker_value) = match self.generate_controls_marker() {
+            Some(marker) => (
+                Some(quote! { marker: #marker, }),
+                Some(quote! { marker: ::std::marker::PhantomData, }),
+            ),
+            None => (None, None),
+        };
+
+        let control_fields = self
+            .params
+            .iter()
+            .map(Param::control_field)
+            .chain(self.submodules.iter().filter_map(Submodule::control_field));
+        let param_names = self.params.iter().map(|param| &param.name);
+        let param_idents = self.params.iter().map(Param::handle_ident).collect::<Vec<_>>();
+        let attachments = self.params.iter().map(Param::attach);
+        let controlled_submodules = self
+            .submodules
+            .iter()
+            .filter(|submodule| submodule.controls.is_some())
+            .collect::<Vec<_>>();
+        let submodule_names = controlled_submodules.iter().map(|submodule| &submodule.field_ident);
+        let submodule_idents = controlled_submodules.iter().map(|submodule| submodule.controls_ident());
+
+        quote! {
+            #vis struct #controls_name #generics #where_clause {
+                #(#control_fields,)*
+                #marker_field
+            }
+
+            impl #impl_generics ::synth_module::ControlledModule for #name #ty_generics #where_clause {
+                type Controls = #controls_name #ty_generics;
+
+                fn build_graph_with_controls(mut self, graph: &mut Graph) -> (Self, Self::Controls) {
+                    #(let #param_idents = #attachments;)*
+
+                    #add_audio_graph_nodes
+                    #connect_audio_graph_nodes
+
+                    let controls = #controls_name {
+                        #(#param_names: #param_idents,)*
+                        #(#submodule_names: #submodule_idents,)*
+                        #marker_value
+                    };
+
+                    (self, controls)
+                }
+            }
+
+            impl #impl_generics SynthModule for #name #ty_generics #where_clause {
+                fn build_graph(self, graph: &mut Graph) -> Self {
+                    ::synth_module::ControlledModule::build_graph_with_controls(self, graph).0
+                }
+            }
+        }
+    }
+
     fn generate_output_accessors(&self) -> TokenStream {
         let outputs_accessors = self
             .outputs
@@ -102,28 +372,41 @@ impl StructImpl {
 impl ToTokens for StructImpl {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let name = &self.name;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         let input_accessors = self.generate_input_accessors();
         let output_accessors = self.generate_output_accessors();
 
-        let input_field_names = self.input_field_names().collect::<Vec<_>>();
-        let output_field_names = self.output_field_names().collect::<Vec<_>>();
+        let port_field_names = self
+            .input_field_names()
+            .chain(self.output_field_names())
+            .collect::<Vec<_>>();
 
-        let connection_graph = ConnectionGraph::new(self.connections.iter());
-        let add_audio_graph_nodes = connection_graph
-            .generate_node_additions(&input_field_names, &output_field_names);
-        let connect_audio_graph_nodes = connection_graph
-            .generate_node_connections(&input_field_names, &output_field_names);
+        let add_audio_graph_nodes = self
+            .connection_graph
+            .generate_node_additions(&port_field_names, &self.submodules, &self.arrays);
+        let connect_audio_graph_nodes = self.connection_graph.generate_node_connections(&self.arrays);
 
-        let impl_tokens = quote! {
-            impl SynthModule for #name {
-                fn build_graph(mut self, graph: &mut Graph) -> Self {
-                    #add_audio_graph_nodes
-                    #connect_audio_graph_nodes
-                    self
+        let has_controls = !self.params.is_empty()
+            || self.submodules.iter().any(|submodule| submodule.controls.is_some());
+
+        let build_graph = if !has_controls {
+            quote! {
+                impl #impl_generics SynthModule for #name #ty_generics #where_clause {
+                    fn build_graph(mut self, graph: &mut Graph) -> Self {
+                        #add_audio_graph_nodes
+                        #connect_audio_graph_nodes
+                        self
+                    }
                 }
             }
+        } else {
+            self.generate_controlled_build_graph(add_audio_graph_nodes, connect_audio_graph_nodes)
+        };
+
+        let impl_tokens = quote! {
+            #build_graph
 
-            impl #name {
+            impl #impl_generics #name #ty_generics #where_clause {
                 #input_accessors
                 #output_accessors
             }
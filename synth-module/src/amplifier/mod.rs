@@ -0,0 +1,3 @@
+mod vca;
+
+pub use vca::VcaModule;
@@ -0,0 +1,41 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::{
+    ops::{Response, Vca},
+    source::Level,
+    util::PassOrDefault,
+};
+
+use dasp_graph::node::Pass;
+use petgraph::graph::NodeIndex;
+
+/// Voltage-controlled amplifier, scaling `audio_in` by the gain that `cv_in` sets through the
+/// response curve.
+#[derive(SynthModule)]
+pub struct VcaModule {
+    #[synth_module(input)]
+    #[synth_module(connect = "vca", slot = 0)]
+    audio: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "vca", slot = 1)]
+    cv: ModuleIO<PassOrDefault<Level>>,
+
+    vca: ModuleIO<Vca>,
+}
+
+impl VcaModule {
+    /// `initial_gain` is the control voltage used while `cv_in` is unpatched.
+    pub fn new(response: Response, initial_gain: f32) -> Self {
+        Self {
+            audio: ModuleIO::new(Pass),
+            cv: ModuleIO::new(PassOrDefault::new(Level::new(initial_gain))),
+            vca: ModuleIO::new(Vca::new(response)),
+        }
+    }
+
+    /// The output is the VCA itself, since an `audio` output field would clash with the input.
+    pub fn audio_out(&self) -> Option<NodeIndex<u32>> {
+        self.vca.index()
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::envelope::{Adsr, Curve};
+
+use dasp_graph::node::Pass;
+use petgraph::graph::NodeIndex;
+
+#[derive(SynthModule)]
+pub struct AdsrEnvelope {
+    #[synth_module(input)]
+    #[synth_module(connect = "env")]
+    gate: ModuleIO<Pass>,
+
+    #[synth_module(output)]
+    env: ModuleIO<Adsr>,
+}
+
+impl AdsrEnvelope {
+    pub fn new(attack: f32, decay: f32, sustain: f32, release: f32, sample_rate: u32) -> Self {
+        Self::with_curve(attack, decay, sustain, release, Curve::Linear, sample_rate)
+    }
+
+    pub fn with_curve(
+        attack: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+        curve: Curve,
+        sample_rate: u32,
+    ) -> Self {
+        Self::from_adsr(Adsr::new(attack, decay, sustain, release, sample_rate).with_curve(curve))
+    }
+
+    /// Wraps an `Adsr` that has already been configured, such as one built `with_trigger` to be
+    /// played straight from a `Clock`.
+    pub fn from_adsr(adsr: Adsr) -> Self {
+        Self {
+            gate: ModuleIO::new(Pass),
+            env: ModuleIO::new(adsr),
+        }
+    }
+}
@@ -0,0 +1,3 @@
+mod svf;
+
+pub use svf::SvfModule;
@@ -0,0 +1,64 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::{
+    filter::{FilterResponse, StateVariableFilter},
+    source::Param,
+    util::{PassOrDefault, Select},
+};
+
+use dasp_graph::node::Pass;
+use petgraph::graph::NodeIndex;
+
+/// State-variable filter with a port for each response.
+///
+/// A single filter computes all four responses, and each output port picks one of its buffers.
+/// While unpatched, the cutoff offset and resonance can be changed through the handles from
+/// `build_graph_with_controls`.
+#[derive(SynthModule)]
+pub struct SvfModule {
+    #[synth_module(input)]
+    #[synth_module(connect = "filter", slot = 0)]
+    audio: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "filter", slot = 1)]
+    #[synth_module(param(range = (-5.0, 5.0), unit = "V/oct", smoothing = 0.005))]
+    cutoff: ModuleIO<PassOrDefault<Param>>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "filter", slot = 2)]
+    #[synth_module(param(range = (0.0, 1.0), smoothing = 0.005))]
+    resonance: ModuleIO<PassOrDefault<Param>>,
+
+    #[synth_module(connect = "lowpass", "highpass", "bandpass", "notch")]
+    filter: ModuleIO<StateVariableFilter>,
+
+    #[synth_module(output)]
+    lowpass: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    highpass: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    bandpass: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    notch: ModuleIO<Select>,
+}
+
+impl SvfModule {
+    pub fn new(cutoff: f32, resonance: f32, sample_rate: u32) -> Self {
+        let filter = StateVariableFilter::new(cutoff, resonance, sample_rate);
+
+        Self {
+            audio: ModuleIO::new(Pass),
+            cutoff: ModuleIO::new(PassOrDefault::new(Param::new(0.0, sample_rate))),
+            resonance: ModuleIO::new(PassOrDefault::new(Param::new(resonance, sample_rate))),
+            filter: ModuleIO::new(filter).with_buffers(FilterResponse::ALL.len()),
+            lowpass: ModuleIO::new(Select::new(StateVariableFilter::LOWPASS_BUFFER)),
+            highpass: ModuleIO::new(Select::new(StateVariableFilter::HIGHPASS_BUFFER)),
+            bandpass: ModuleIO::new(Select::new(StateVariableFilter::BANDPASS_BUFFER)),
+            notch: ModuleIO::new(Select::new(StateVariableFilter::NOTCH_BUFFER)),
+        }
+    }
+}
@@ -1,14 +1,35 @@
 pub use synth_module_derive::SynthModule;
 
+// Lets the derive refer to this crate by name from inside it too.
+extern crate self as synth_module;
+
 use dasp_graph::{BoxedNode, NodeData};
 use petgraph::Directed;
 
+pub mod amplifier;
+pub mod envelope;
+pub mod filter;
+pub mod midi;
+pub mod modulation;
 pub mod oscillator;
 pub mod port;
+pub mod render;
 pub mod sequencer;
+pub mod voice;
 
-type Graph = petgraph::Graph<NodeData<BoxedNode>, (), Directed, u32>;
+pub type Graph = petgraph::Graph<NodeData<BoxedNode>, (), Directed, u32>;
 
 pub trait SynthModule {
     fn build_graph(self, graph: &mut Graph) -> Self;
 }
+
+/// A module with parameters that can be changed while the graph runs.
+///
+/// Derived for modules with `#[synth_module(param(...))]` fields or
+/// `#[synth_module(submodule(controls))]` fields. `Controls` is a generated struct holding a
+/// `port::ParamHandle` per parameter and the controls of each such submodule.
+pub trait ControlledModule: SynthModule + Sized {
+    type Controls;
+
+    fn build_graph_with_controls(self, graph: &mut Graph) -> (Self, Self::Controls);
+}
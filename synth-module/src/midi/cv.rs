@@ -0,0 +1,44 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::{
+    midi::{MidiCv, MidiTransport},
+    util::Select,
+};
+
+use petgraph::graph::NodeIndex;
+
+/// Plays a patch from live MIDI. Build the `MidiCv` with `with_cc` to have controllers drive
+/// `Level`s elsewhere in the patch.
+#[derive(SynthModule)]
+pub struct MidiCvModule<T: MidiTransport + 'static> {
+    #[synth_module(connect = "v_oct", "gate", "velocity", "pitch_bend", "mod_wheel")]
+    midi: ModuleIO<MidiCv<T>>,
+
+    #[synth_module(output)]
+    v_oct: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    gate: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    velocity: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    pitch_bend: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    mod_wheel: ModuleIO<Select>,
+}
+
+impl<T: MidiTransport + 'static> MidiCvModule<T> {
+    pub fn new(midi: MidiCv<T>) -> Self {
+        Self {
+            midi: ModuleIO::new(midi).with_buffers(MidiCv::<T>::BUFFERS),
+            v_oct: ModuleIO::new(Select::new(MidiCv::<T>::V_OCT_BUFFER)),
+            gate: ModuleIO::new(Select::new(MidiCv::<T>::GATE_BUFFER)),
+            velocity: ModuleIO::new(Select::new(MidiCv::<T>::VELOCITY_BUFFER)),
+            pitch_bend: ModuleIO::new(Select::new(MidiCv::<T>::PITCH_BEND_BUFFER)),
+            mod_wheel: ModuleIO::new(Select::new(MidiCv::<T>::MOD_WHEEL_BUFFER)),
+        }
+    }
+}
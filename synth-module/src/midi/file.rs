@@ -0,0 +1,33 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::{midi::MidiFilePlayer, util::Select};
+
+use petgraph::graph::NodeIndex;
+
+/// Plays a MIDI file into a patch, with `v_oct_out` and `gate_out` ready for an oscillator's
+/// `v_oct_in` and an envelope's `gate_in`.
+#[derive(SynthModule)]
+pub struct MidiFileModule {
+    #[synth_module(connect = "v_oct", "gate", "velocity")]
+    player: ModuleIO<MidiFilePlayer>,
+
+    #[synth_module(output)]
+    v_oct: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    gate: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    velocity: ModuleIO<Select>,
+}
+
+impl MidiFileModule {
+    pub fn new(player: MidiFilePlayer) -> Self {
+        Self {
+            player: ModuleIO::new(player).with_buffers(3),
+            v_oct: ModuleIO::new(Select::new(MidiFilePlayer::V_OCT_BUFFER)),
+            gate: ModuleIO::new(Select::new(MidiFilePlayer::GATE_BUFFER)),
+            velocity: ModuleIO::new(Select::new(MidiFilePlayer::VELOCITY_BUFFER)),
+        }
+    }
+}
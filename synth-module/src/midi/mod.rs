@@ -0,0 +1,5 @@
+mod cv;
+mod file;
+
+pub use cv::MidiCvModule;
+pub use file::MidiFileModule;
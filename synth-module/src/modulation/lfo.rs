@@ -0,0 +1,32 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::source::{Lfo, LfoRate, LfoShape};
+
+use dasp_graph::node::Pass;
+use petgraph::graph::NodeIndex;
+
+/// LFO whose `cv_out` port can be patched into `v_oct_in`, a filter's `cutoff_in`, or any other
+/// CV input. Build it `from_lfo` to set the polarity, depth and phase offset; with a bipolar shape
+/// the depth is the swing in octaves either side of the pitch.
+#[derive(SynthModule)]
+pub struct LfoModule {
+    #[synth_module(input)]
+    #[synth_module(connect = "cv")]
+    clock: ModuleIO<Pass>,
+
+    #[synth_module(output)]
+    cv: ModuleIO<Lfo>,
+}
+
+impl LfoModule {
+    pub fn new(rate: LfoRate, shape: LfoShape, sample_rate: u32) -> Self {
+        Self::from_lfo(Lfo::new(rate, shape, sample_rate))
+    }
+
+    pub fn from_lfo(lfo: Lfo) -> Self {
+        Self {
+            clock: ModuleIO::new(Pass),
+            cv: ModuleIO::new(lfo),
+        }
+    }
+}
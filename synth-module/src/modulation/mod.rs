@@ -0,0 +1,3 @@
+mod lfo;
+
+pub use lfo::LfoModule;
@@ -10,9 +10,23 @@ use petgraph::graph::NodeIndex;
 #[derive(SynthModule)]
 pub struct DeriveOscillator {
     #[synth_module(input)]
-    #[synth_module(connect = "sine", "square", "saw", "triangle")]
+    #[synth_module(connect = "sine", "square", "saw", "triangle", slot = 0)]
     v_oct: ModuleIO<PassOrDefault<Level>>,
 
+    #[synth_module(input)]
+    #[synth_module(connect = "square", slot = 1)]
+    pw: ModuleIO<PassOrDefault<Level>>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "sine", "saw", "triangle", slot = 1)]
+    #[synth_module(connect = "square", slot = 2)]
+    sync: ModuleIO<PassOrDefault<Level>>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "sine", "saw", "triangle", slot = 2)]
+    #[synth_module(connect = "square", slot = 3)]
+    fm: ModuleIO<PassOrDefault<Level>>,
+
     #[synth_module(output)]
     sine: ModuleIO<Sine>,
 
@@ -30,10 +44,27 @@ impl DeriveOscillator {
     pub fn new(freq: f32, sample_rate: u32) -> Self {
         Self {
             v_oct: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            pw: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            sync: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            fm: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
             sine: ModuleIO::new(Sine::new(freq, sample_rate)),
             square: ModuleIO::new(Square::new(freq, sample_rate)),
             saw: ModuleIO::new(Saw::new(freq, sample_rate)),
             triangle: ModuleIO::new(Triangle::new(freq, sample_rate)),
         }
     }
+
+    /// Like `new`, but with PolyBLEP/PolyBLAMP correction on the saw, square and triangle outputs.
+    pub fn band_limited(freq: f32, sample_rate: u32) -> Self {
+        Self {
+            v_oct: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            pw: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            sync: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            fm: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            sine: ModuleIO::new(Sine::new(freq, sample_rate)),
+            square: ModuleIO::new(Square::new(freq, sample_rate).band_limited()),
+            saw: ModuleIO::new(Saw::new(freq, sample_rate).band_limited()),
+            triangle: ModuleIO::new(Triangle::new(freq, sample_rate).band_limited()),
+        }
+    }
 }
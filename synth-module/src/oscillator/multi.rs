@@ -1,4 +1,4 @@
-use crate::{port::ModuleIO, Graph};
+use crate::{port::ModuleIO, Graph, SynthModule};
 
 use synth_node::{
     source::{Level, Saw, Sine, Square, Triangle},
@@ -7,11 +7,22 @@ use synth_node::{
 
 use petgraph::graph::NodeIndex;
 
+#[derive(SynthModule)]
 pub struct MultiOscillator {
+    #[synth_module(input)]
+    #[synth_module(connect = "sine", "square", "saw", "triangle")]
     v_oct: ModuleIO<PassOrDefault<Level>>,
+
+    #[synth_module(output)]
     sine: ModuleIO<Sine>,
+
+    #[synth_module(output)]
     square: ModuleIO<Square>,
+
+    #[synth_module(output)]
     saw: ModuleIO<Saw>,
+
+    #[synth_module(output)]
     triangle: ModuleIO<Triangle>,
 }
 
@@ -26,46 +37,14 @@ impl MultiOscillator {
         }
     }
 
-    pub fn build_graph(mut self, graph: &mut Graph) -> Self {
-        self.v_oct.connect(graph);
-        self.sine.connect(graph);
-        self.square.connect(graph);
-        self.saw.connect(graph);
-        self.triangle.connect(graph);
-
-        graph.add_edge(self.v_oct.index().unwrap(), self.sine.index().unwrap(), ());
-        graph.add_edge(
-            self.v_oct.index().unwrap(),
-            self.square.index().unwrap(),
-            (),
-        );
-        graph.add_edge(self.v_oct.index().unwrap(), self.saw.index().unwrap(), ());
-        graph.add_edge(
-            self.v_oct.index().unwrap(),
-            self.triangle.index().unwrap(),
-            (),
-        );
-
-        self
-    }
-
-    pub fn v_oct_in(&self) -> Option<NodeIndex<u32>> {
-        self.v_oct.index()
-    }
-
-    pub fn sine_out(&self) -> Option<NodeIndex<u32>> {
-        self.sine.index()
-    }
-
-    pub fn square_out(&self) -> Option<NodeIndex<u32>> {
-        self.square.index()
-    }
-
-    pub fn saw_out(&self) -> Option<NodeIndex<u32>> {
-        self.saw.index()
-    }
-
-    pub fn triangle_out(&self) -> Option<NodeIndex<u32>> {
-        self.triangle.index()
+    /// Like `new`, but with PolyBLEP/PolyBLAMP correction on the saw, square and triangle outputs.
+    pub fn band_limited(freq: f32, sample_rate: u32) -> Self {
+        Self {
+            v_oct: ModuleIO::new(PassOrDefault::new(Level::new(0.0))),
+            sine: ModuleIO::new(Sine::new(freq, sample_rate)),
+            square: ModuleIO::new(Square::new(freq, sample_rate).band_limited()),
+            saw: ModuleIO::new(Saw::new(freq, sample_rate).band_limited()),
+            triangle: ModuleIO::new(Triangle::new(freq, sample_rate).band_limited()),
+        }
     }
 }
@@ -1,8 +1,9 @@
-use dasp_graph::{Node, NodeData};
+use dasp_graph::{Buffer, Node, NodeData};
 use petgraph::graph::NodeIndex;
 
 pub struct ModuleIO<T: Node + 'static> {
     inner: Impl<T>,
+    buffers: usize,
 }
 
 enum Impl<T: Node + 'static> {
@@ -18,20 +19,30 @@ impl<T: Node + 'static> ModuleIO<T> {
     pub fn connected(index: NodeIndex<u32>) -> Self {
         Self {
             inner: Impl::Connected(index),
+            buffers: 1,
         }
     }
 
     pub fn disconnected(node: T) -> Self {
         Self {
             inner: Impl::Disconnected(Some(node)),
+            buffers: 1,
         }
     }
 
+    /// Gives the node `buffers` output buffers when it is connected, for nodes that write
+    /// several channels.
+    pub fn with_buffers(mut self, buffers: usize) -> Self {
+        self.buffers = buffers;
+        self
+    }
+
     pub fn connect(&mut self, graph: &mut crate::Graph) {
         let inner = match &mut self.inner {
             Impl::Disconnected(node) => {
                 if let Some(node) = node.take() {
-                    let idx = graph.add_node(NodeData::boxed1(node));
+                    let buffers = vec![Buffer::SILENT; self.buffers];
+                    let idx = graph.add_node(NodeData::boxed(node, buffers));
                     Some(Impl::Connected(idx))
                 } else {
                     None
@@ -45,6 +56,14 @@ impl<T: Node + 'static> ModuleIO<T> {
         }
     }
 
+    /// The node this port will add to the graph, until it has been connected.
+    pub fn node_mut(&mut self) -> Option<&mut T> {
+        match &mut self.inner {
+            Impl::Disconnected(node) => node.as_mut(),
+            Impl::Connected(_) => None,
+        }
+    }
+
     pub fn index(&self) -> Option<NodeIndex<u32>> {
         match &self.inner {
             Impl::Disconnected(_) => None,
@@ -1,3 +1,6 @@
 mod io;
+mod param;
 
 pub use io::ModuleIO;
+pub use param::ParamHandle;
+pub use synth_node::source::ParamSpec;
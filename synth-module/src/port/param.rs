@@ -0,0 +1,87 @@
+use super::ModuleIO;
+
+use synth_node::source::{LevelCommand, ParamSpec, ParamTarget};
+
+use dasp_graph::Node;
+
+use std::sync::mpsc::Sender;
+
+/// Changes a module parameter while the graph runs. Handles can be cloned and sent to other
+/// threads.
+#[derive(Clone, Debug)]
+pub struct ParamHandle {
+    name: &'static str,
+    unit: &'static str,
+    min: f32,
+    max: f32,
+    default: f32,
+    tx: Sender<LevelCommand>,
+}
+
+impl ParamHandle {
+    /// Hands the level of the node behind `io` over to a new handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `io` has already been connected to a graph.
+    pub fn attach<T>(
+        io: &mut ModuleIO<T>,
+        name: &'static str,
+        unit: &'static str,
+        spec: ParamSpec,
+    ) -> Self
+    where
+        T: Node + ParamTarget + 'static,
+    {
+        let node = io
+            .node_mut()
+            .expect("parameters must be attached before the port is connected");
+        let (tx, default) = node.attach(spec);
+
+        Self {
+            name,
+            unit,
+            min: spec.min,
+            max: spec.max,
+            default,
+            tx,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn unit(&self) -> &'static str {
+        self.unit
+    }
+
+    pub fn range(&self) -> (f32, f32) {
+        (self.min, self.max)
+    }
+
+    pub fn default_value(&self) -> f32 {
+        self.default
+    }
+
+    /// Sets the value, clamped to the parameter's range.
+    pub fn set(&self, value: f32) {
+        let _ = self
+            .tx
+            .send(LevelCommand::SetLevel(value.clamp(self.min, self.max)));
+    }
+
+    /// Sets the value from a position between 0 and 1 across the parameter's range.
+    pub fn set_normalized(&self, position: f32) {
+        self.set(self.min + position.clamp(0.0, 1.0) * (self.max - self.min));
+    }
+
+    /// Moves the value by `delta`, stopping at the ends of the range.
+    pub fn nudge(&self, delta: f32) {
+        let _ = self.tx.send(LevelCommand::DeltaLevel(delta));
+    }
+
+    pub fn reset(&self) {
+        self.set(self.default);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::Graph;
+
+use dasp_graph::{Buffer, Processor};
+use petgraph::graph::NodeIndex;
+
+use std::io::{self, Write};
+
+/// Drives a graph offline for a fixed number of `Buffer::LEN` blocks.
+///
+/// Nothing here waits on an audio device, so a render runs as fast as the graph can be processed
+/// and produces the same output every time for the same graph.
+pub struct Renderer<'a> {
+    graph: &'a mut Graph,
+    processor: Processor<Graph>,
+    node: NodeIndex<u32>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(graph: &'a mut Graph, node: NodeIndex<u32>) -> Self {
+        let processor = Processor::with_capacity(graph.node_count());
+
+        Self {
+            graph,
+            processor,
+            node,
+        }
+    }
+
+    /// Number of blocks needed to cover at least `frames` sample frames.
+    pub fn blocks_for(frames: usize) -> usize {
+        frames.div_ceil(Buffer::LEN)
+    }
+
+    /// Processes `blocks` blocks, discarding the output of the rendered node.
+    pub fn run(&mut self, blocks: usize) {
+        self.render_with(blocks, |_| {});
+    }
+
+    /// Processes `blocks` blocks, handing the rendered node's buffers to `f` after each block.
+    pub fn render_with<F>(&mut self, blocks: usize, mut f: F)
+    where
+        F: FnMut(&[Buffer]),
+    {
+        for _ in 0..blocks {
+            self.processor.process(self.graph, self.node);
+            f(&self.graph[self.node].buffers);
+        }
+    }
+
+    /// Processes `blocks` blocks and collects the rendered samples, one `Vec` per channel.
+    pub fn render(&mut self, blocks: usize) -> Vec<Vec<f32>> {
+        let channels = self.graph[self.node].buffers.len();
+        let mut rendered = vec![Vec::with_capacity(blocks * Buffer::LEN); channels];
+
+        self.render_with(blocks, |buffers| {
+            for (channel, buffer) in rendered.iter_mut().zip(buffers) {
+                channel.extend_from_slice(buffer);
+            }
+        });
+
+        rendered
+    }
+
+    /// Processes `blocks` blocks and streams the rendered samples to `writer` as interleaved
+    /// little-endian `f32`s.
+    pub fn render_to<W: Write>(&mut self, blocks: usize, writer: &mut W) -> io::Result<()> {
+        for _ in 0..blocks {
+            self.processor.process(self.graph, self.node);
+
+            let buffers = &self.graph[self.node].buffers;
+
+            for i in 0..Buffer::LEN {
+                for buffer in buffers {
+                    writer.write_all(&buffer[i].to_le_bytes())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use synth_node::source::Saw;
+
+    use dasp_graph::NodeData;
+
+    fn render_saw(blocks: usize) -> Vec<Vec<f32>> {
+        let mut graph = Graph::new();
+        let saw = graph.add_node(NodeData::boxed1(Saw::new(220.0, 44_100)));
+
+        Renderer::new(&mut graph, saw).render(blocks)
+    }
+
+    #[test]
+    fn blocks_cover_frames() {
+        assert_eq!(Renderer::blocks_for(0), 0);
+        assert_eq!(Renderer::blocks_for(1), 1);
+        assert_eq!(Renderer::blocks_for(Buffer::LEN), 1);
+        assert_eq!(Renderer::blocks_for(Buffer::LEN + 1), 2);
+    }
+
+    #[test]
+    fn render_collects_every_block() {
+        let rendered = render_saw(10);
+
+        assert_eq!(rendered.len(), 1);
+        assert_eq!(rendered[0].len(), 10 * Buffer::LEN);
+    }
+
+    #[test]
+    fn render_is_deterministic() {
+        assert_eq!(render_saw(20), render_saw(20));
+    }
+
+    #[test]
+    fn render_to_interleaves_little_endian_samples() {
+        let mut graph = Graph::new();
+        let saw = graph.add_node(NodeData::boxed1(Saw::new(220.0, 44_100)));
+
+        let mut bytes = Vec::new();
+        Renderer::new(&mut graph, saw)
+            .render_to(2, &mut bytes)
+            .unwrap();
+
+        let samples = bytes
+            .chunks_exact(4)
+            .map(|sample| f32::from_le_bytes(sample.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(samples, render_saw(2)[0]);
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{port::ModuleIO, Graph, SynthModule};
+
+use synth_node::{
+    sequencer::{Step, StepSequence},
+    util::Select,
+};
+
+use dasp_graph::node::Pass;
+use petgraph::graph::NodeIndex;
+
+/// Step sequencer with a gate per step, for driving an oscillator's `v_oct_in` and an envelope's
+/// `gate_in` together. Build it `from_sequence` to set the direction, active length, or a command
+/// channel for editing steps while it plays.
+///
+/// The clock rises at `Clock::HIGH`, which a square LFO swinging between 0 and 1 never reaches.
+/// Build the sequence `with_threshold` to clock it from one.
+#[derive(SynthModule)]
+pub struct GateSequencer<const N: usize> {
+    #[synth_module(input)]
+    #[synth_module(connect = "sequence", slot = 0)]
+    clock: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "sequence", slot = 1)]
+    reset: ModuleIO<Pass>,
+
+    #[synth_module(connect = "v_oct", "gate")]
+    sequence: ModuleIO<StepSequence<N>>,
+
+    #[synth_module(output)]
+    v_oct: ModuleIO<Select>,
+
+    #[synth_module(output)]
+    gate: ModuleIO<Select>,
+}
+
+impl<const N: usize> GateSequencer<N> {
+    pub fn new(steps: [Step; N]) -> Self {
+        Self::from_sequence(StepSequence::new(steps))
+    }
+
+    pub fn from_sequence(sequence: StepSequence<N>) -> Self {
+        Self {
+            clock: ModuleIO::new(Pass),
+            reset: ModuleIO::new(Pass),
+            sequence: ModuleIO::new(sequence).with_buffers(2),
+            v_oct: ModuleIO::new(Select::new(StepSequence::<N>::VALUE_BUFFER)),
+            gate: ModuleIO::new(Select::new(StepSequence::<N>::GATE_BUFFER)),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+mod gate;
 mod step;
 
+pub use gate::GateSequencer;
 pub use step::StepSequencer;
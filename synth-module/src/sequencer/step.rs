@@ -1,73 +1,123 @@
-use crate::{port::ModuleIO, Graph};
+use crate::{port::ModuleIO, Graph, SynthModule};
 
-use synth_node::{branch::SequentialSwitch, source::Level, util::PassOrDefault};
+use synth_node::{
+    branch::{SequentialSwitch, SwitchMode},
+    source::Level,
+    util::PassOrDefault,
+};
 
 use dasp_graph::node::Pass;
 use petgraph::graph::NodeIndex;
 
+/// Cycles through `N` patchable levels. In `SwitchMode::Index` the `clock_in` port takes an index
+/// CV instead of a clock.
+///
+/// By default the clock rises at `Clock::HIGH / 2.0` with 1V of hysteresis, which suits `Clock`
+/// and other gates at `Clock::HIGH`. A square LFO swinging between 0 and 1, or -1 and 1, never
+/// gets there, so build the sequencer `with_threshold` to clock it from one.
+#[derive(SynthModule)]
 pub struct StepSequencer<const N: usize> {
-    clock_in: ModuleIO<Pass>,
+    #[synth_module(input)]
+    #[synth_module(connect = "level_switch", slot = 0)]
+    clock: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "level_switch", slot = 1)]
+    reset: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "level_switch")]
     levels: [ModuleIO<PassOrDefault<Level>>; N],
+
+    #[synth_module(connect = "v_oct")]
     level_switch: ModuleIO<SequentialSwitch>,
-    v_oct_out: ModuleIO<Pass>,
+
+    #[synth_module(output)]
+    v_oct: ModuleIO<Pass>,
 }
 
 impl<const N: usize> StepSequencer<N> {
     pub fn new(levels: [Level; N]) -> Self {
-        let clock_in = ModuleIO::new(Pass);
-        let level_switch = ModuleIO::new(SequentialSwitch::new(N));
-        let levels = levels.map(|level| ModuleIO::new(PassOrDefault::new(level)));
-        let v_oct_out = ModuleIO::new(Pass);
+        Self::with_mode(levels, SwitchMode::Clock)
+    }
+
+    pub fn with_mode(levels: [Level; N], mode: SwitchMode) -> Self {
+        Self::from_switch(levels, SequentialSwitch::new(N).with_mode(mode))
+    }
+
+    /// Like `with_mode`, with the clock and reset rising at `threshold` and falling `hysteresis`
+    /// below it. `0.5` and `0.25` suit a unipolar square LFO.
+    pub fn with_threshold(
+        levels: [Level; N],
+        mode: SwitchMode,
+        threshold: f32,
+        hysteresis: f32,
+    ) -> Self {
+        let switch = SequentialSwitch::new(N)
+            .with_mode(mode)
+            .with_threshold(threshold, hysteresis);
+
+        Self::from_switch(levels, switch)
+    }
 
+    fn from_switch(levels: [Level; N], switch: SequentialSwitch) -> Self {
         Self {
-            clock_in,
-            level_switch,
-            levels,
-            v_oct_out,
+            clock: ModuleIO::new(Pass),
+            reset: ModuleIO::new(Pass),
+            levels: levels.map(|level| ModuleIO::new(PassOrDefault::new(level))),
+            level_switch: ModuleIO::new(switch.with_reset()),
+            v_oct: ModuleIO::new(Pass),
         }
     }
+}
 
-    pub fn build_graph(mut self, graph: &mut Graph) -> Self {
-        self.clock_in.connect(graph);
-        self.level_switch.connect(graph);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modulation::LfoModule;
 
-        for level in self.levels.iter_mut() {
-            level.connect(graph);
-        }
+    use synth_node::source::{Lfo, LfoRate, LfoShape, Polarity};
 
-        self.v_oct_out.connect(graph);
+    use dasp_graph::{Buffer, Processor};
 
-        for level in self.levels.iter().rev() {
-            graph.add_edge(
-                level.index().unwrap(),
-                self.level_switch.index().unwrap(),
-                (),
-            );
-        }
+    const SAMPLE_RATE: u32 = 44_100;
 
-        graph.add_edge(
-            self.clock_in.index().unwrap(),
-            self.level_switch.index().unwrap(),
-            (),
-        );
-        graph.add_edge(
-            self.level_switch.index().unwrap(),
-            self.v_oct_out.index().unwrap(),
-            (),
-        );
-
-        self
-    }
+    /// Steps a 3 level sequencer from a unipolar square LFO, returning the level at the start of
+    /// every block.
+    fn clocked_by_lfo(sequencer: StepSequencer<3>) -> Vec<f32> {
+        let mut graph = Graph::new();
+
+        let lfo = Lfo::new(LfoRate::Hz(100.0), LfoShape::Square, SAMPLE_RATE)
+            .with_polarity(Polarity::Unipolar);
+        let lfo = LfoModule::from_lfo(lfo).build_graph(&mut graph);
+        let sequencer = sequencer.build_graph(&mut graph);
+        graph.add_edge(lfo.cv_out().unwrap(), sequencer.clock_in().unwrap(), ());
 
-    pub fn clock_in(&self) -> Option<NodeIndex<u32>> {
-        self.clock_in.index()
+        let out = sequencer.v_oct_out().unwrap();
+        let mut processor = Processor::with_capacity(64);
+
+        (0..SAMPLE_RATE as usize / 10 / Buffer::LEN)
+            .map(|_| {
+                processor.process(&mut graph, out);
+                graph[out].buffers[0][0]
+            })
+            .collect()
     }
 
-    pub fn v_oct_in(&self, index: usize) -> Option<NodeIndex<u32>> {
-        self.levels.get(index).and_then(|level| level.index())
+    fn levels() -> [Level; 3] {
+        [Level::new(1.0), Level::new(2.0), Level::new(3.0)]
     }
 
-    pub fn v_oct_out(&self) -> Option<NodeIndex<u32>> {
-        self.v_oct_out.index()
+    #[test]
+    fn square_lfo_needs_a_lower_threshold() {
+        let steps = clocked_by_lfo(StepSequencer::new(levels()));
+        assert!(steps.iter().all(|&level| level == 1.0));
+
+        let sequencer = StepSequencer::with_threshold(levels(), SwitchMode::Clock, 0.5, 0.25);
+        let steps = clocked_by_lfo(sequencer);
+
+        for level in [1.0, 2.0, 3.0] {
+            assert!(steps.contains(&level));
+        }
     }
 }
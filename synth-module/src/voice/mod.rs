@@ -0,0 +1,5 @@
+mod poly;
+mod subtractive;
+
+pub use poly::{PolyVoices, Voice};
+pub use subtractive::SubtractiveVoice;
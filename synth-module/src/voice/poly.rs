@@ -0,0 +1,203 @@
+use crate::{port::ModuleIO, ControlledModule, Graph, SynthModule};
+
+use synth_node::{
+    util::Select,
+    voice::{StealPolicy, VoiceAllocator, VoiceMeter},
+};
+
+use dasp_graph::node::Sum;
+use petgraph::graph::NodeIndex;
+
+/// A module that can be played as one voice of a `PolyVoices`.
+///
+/// The ports are looked up after the voice's graph has been built.
+pub trait Voice: SynthModule {
+    /// Takes the note's pitch in volts per octave.
+    fn pitch_input(&self) -> Option<NodeIndex<u32>>;
+
+    /// Takes a gate that is held at `Clock::HIGH` for as long as the note is.
+    fn gate_input(&self) -> Option<NodeIndex<u32>>;
+
+    /// Takes the note's velocity between 0 and 1, for voices that respond to it.
+    fn velocity_input(&self) -> Option<NodeIndex<u32>> {
+        None
+    }
+
+    fn audio_output(&self) -> Option<NodeIndex<u32>>;
+}
+
+/// Plays notes on `N` copies of a voice and sums them on `audio_out`.
+///
+/// Notes are sent to the `VoiceAllocator`, so build it `from_allocator` with a channel to play
+/// the module.
+pub struct PolyVoices<V: Voice, const N: usize> {
+    allocator: ModuleIO<VoiceAllocator>,
+    voices: [V; N],
+    pitches: [ModuleIO<Select>; N],
+    gates: [ModuleIO<Select>; N],
+    velocities: [ModuleIO<Select>; N],
+    meters: [ModuleIO<VoiceMeter>; N],
+    mix: ModuleIO<Sum>,
+}
+
+impl<V: Voice, const N: usize> PolyVoices<V, N> {
+    /// Makes each voice by calling `voice` with its index.
+    pub fn new<F>(policy: StealPolicy, voice: F) -> Self
+    where
+        F: FnMut(usize) -> V,
+    {
+        Self::from_allocator(VoiceAllocator::new(N, policy), voice)
+    }
+
+    pub fn from_allocator<F>(allocator: VoiceAllocator, voice: F) -> Self
+    where
+        F: FnMut(usize) -> V,
+    {
+        assert_eq!(allocator.voices(), N);
+
+        let select = |buffer: usize| {
+            std::array::from_fn(|index| {
+                ModuleIO::new(Select::new(VoiceAllocator::buffer_index(index, buffer)))
+            })
+        };
+
+        Self {
+            pitches: select(VoiceAllocator::PITCH_BUFFER),
+            gates: select(VoiceAllocator::GATE_BUFFER),
+            velocities: select(VoiceAllocator::VELOCITY_BUFFER),
+            meters: std::array::from_fn(|index| ModuleIO::new(allocator.meter(index))),
+            allocator: ModuleIO::new(allocator).with_buffers(N * VoiceAllocator::BUFFERS_PER_VOICE),
+            voices: std::array::from_fn(voice),
+            mix: ModuleIO::new(Sum),
+        }
+    }
+
+    pub fn voice(&self, index: usize) -> Option<&V> {
+        self.voices.get(index)
+    }
+
+    pub fn audio_out(&self) -> Option<NodeIndex<u32>> {
+        self.mix.index()
+    }
+}
+
+impl<V: Voice, const N: usize> PolyVoices<V, N> {
+    /// Patches the allocator into the voices, once their graphs have been built, and sums them
+    /// into the mix.
+    fn connect_voices(&mut self, graph: &mut Graph) {
+        let allocator = self.allocator.index().unwrap();
+        let mix = self.mix.index().unwrap();
+
+        for (index, voice) in self.voices.iter().enumerate() {
+            let controls = [
+                (&mut self.pitches[index], voice.pitch_input()),
+                (&mut self.gates[index], voice.gate_input()),
+                (&mut self.velocities[index], voice.velocity_input()),
+            ];
+
+            for (select, input) in controls {
+                if let Some(input) = input {
+                    select.connect(graph);
+                    graph.add_edge(allocator, select.index().unwrap(), ());
+                    graph.add_edge(select.index().unwrap(), input, ());
+                }
+            }
+
+            let meter = &mut self.meters[index];
+            meter.connect(graph);
+
+            if let Some(output) = voice.audio_output() {
+                graph.add_edge(output, meter.index().unwrap(), ());
+            }
+
+            graph.add_edge(meter.index().unwrap(), mix, ());
+        }
+    }
+}
+
+impl<V: Voice, const N: usize> SynthModule for PolyVoices<V, N> {
+    fn build_graph(mut self, graph: &mut Graph) -> Self {
+        self.allocator.connect(graph);
+        self.mix.connect(graph);
+
+        self.voices = self.voices.map(|voice| voice.build_graph(graph));
+        self.connect_voices(graph);
+
+        self
+    }
+}
+
+/// Hands back the controls of every voice, in voice order.
+impl<V: Voice + ControlledModule, const N: usize> ControlledModule for PolyVoices<V, N> {
+    type Controls = [V::Controls; N];
+
+    fn build_graph_with_controls(mut self, graph: &mut Graph) -> (Self, Self::Controls) {
+        self.allocator.connect(graph);
+        self.mix.connect(graph);
+
+        let mut controls = Vec::with_capacity(N);
+
+        self.voices = self.voices.map(|voice| {
+            let (voice, voice_controls) = voice.build_graph_with_controls(graph);
+            controls.push(voice_controls);
+            voice
+        });
+        self.connect_voices(graph);
+
+        let mut controls = controls.into_iter();
+        let controls = std::array::from_fn(|_| controls.next().unwrap());
+
+        (self, controls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        amplifier::VcaModule, envelope::AdsrEnvelope, filter::SvfModule,
+        oscillator::DeriveOscillator, voice::SubtractiveVoice,
+    };
+
+    use synth_node::ops::Response;
+
+    use dasp_graph::node::Pass;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// A generic module whose controlled submodule's type mentions its type parameter.
+    #[derive(SynthModule)]
+    struct Layered<V: Voice + ControlledModule, const N: usize> {
+        #[synth_module(submodule(controls))]
+        #[synth_module(connect = "audio", from = "audio")]
+        voices: PolyVoices<V, N>,
+
+        #[synth_module(output)]
+        audio: ModuleIO<Pass>,
+    }
+
+    fn voice(_: usize) -> SubtractiveVoice {
+        SubtractiveVoice::new(
+            DeriveOscillator::new(110.0, SAMPLE_RATE),
+            SvfModule::new(1200.0, 0.3, SAMPLE_RATE),
+            AdsrEnvelope::new(0.01, 0.1, 0.5, 0.1, SAMPLE_RATE),
+            VcaModule::new(Response::Linear, 0.0),
+            SAMPLE_RATE,
+        )
+    }
+
+    #[test]
+    fn generic_module_forwards_submodule_controls() {
+        let layered = Layered {
+            voices: PolyVoices::<_, 3>::new(StealPolicy::Oldest, voice),
+            audio: ModuleIO::new(Pass),
+        };
+
+        let mut graph = Graph::new();
+        let (layered, controls): (_, LayeredControls<SubtractiveVoice, 3>) =
+            layered.build_graph_with_controls(&mut graph);
+
+        assert!(layered.audio_out().is_some());
+        assert_eq!(controls.voices.len(), 3);
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{
+    amplifier::VcaModule, envelope::AdsrEnvelope, filter::SvfModule, oscillator::DeriveOscillator,
+    port::ModuleIO, voice::Voice, Graph, SynthModule,
+};
+
+use synth_node::{source::Param, util::PassOrDefault};
+
+use dasp_graph::node::Pass;
+use petgraph::graph::NodeIndex;
+
+/// A saw wave through a lowpass filter, shaped by an envelope on a VCA.
+///
+/// `v_oct_in` sets the pitch and `gate_in` opens the envelope, so the voice can be played
+/// straight from a sequencer or as one voice of a `PolyVoices`. `cutoff_in` offsets the filter
+/// cutoff in volts per octave, and while unpatched the `cutoff` control sets the offset instead.
+#[derive(SynthModule)]
+pub struct SubtractiveVoice {
+    #[synth_module(input)]
+    #[synth_module(connect = "oscillator.v_oct")]
+    v_oct: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "envelope.gate")]
+    gate: ModuleIO<Pass>,
+
+    #[synth_module(input)]
+    #[synth_module(connect = "filter.cutoff")]
+    #[synth_module(param(range = (-5.0, 5.0), unit = "V/oct", smoothing = 0.005))]
+    cutoff: ModuleIO<PassOrDefault<Param>>,
+
+    #[synth_module(submodule)]
+    #[synth_module(connect = "filter.audio", from = "saw")]
+    oscillator: DeriveOscillator,
+
+    #[synth_module(submodule)]
+    #[synth_module(connect = "vca.audio", from = "lowpass")]
+    filter: SvfModule,
+
+    #[synth_module(submodule)]
+    #[synth_module(connect = "vca.cv", from = "env")]
+    envelope: AdsrEnvelope,
+
+    #[synth_module(submodule)]
+    #[synth_module(connect = "audio", from = "audio")]
+    vca: VcaModule,
+
+    #[synth_module(output)]
+    audio: ModuleIO<Pass>,
+}
+
+impl SubtractiveVoice {
+    /// The envelope drives the VCA's CV, so `vca` is usually built with an initial gain of 0.
+    pub fn new(
+        oscillator: DeriveOscillator,
+        filter: SvfModule,
+        envelope: AdsrEnvelope,
+        vca: VcaModule,
+        sample_rate: u32,
+    ) -> Self {
+        Self {
+            v_oct: ModuleIO::new(Pass),
+            gate: ModuleIO::new(Pass),
+            cutoff: ModuleIO::new(PassOrDefault::new(Param::new(0.0, sample_rate))),
+            oscillator,
+            filter,
+            envelope,
+            vca,
+            audio: ModuleIO::new(Pass),
+        }
+    }
+}
+
+impl Voice for SubtractiveVoice {
+    fn pitch_input(&self) -> Option<NodeIndex<u32>> {
+        self.v_oct_in()
+    }
+
+    fn gate_input(&self) -> Option<NodeIndex<u32>> {
+        self.gate_in()
+    }
+
+    fn audio_output(&self) -> Option<NodeIndex<u32>> {
+        self.audio_out()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{voice::PolyVoices, ControlledModule};
+
+    use synth_node::{
+        ops::Response,
+        source::{Clock, Level},
+        voice::StealPolicy,
+    };
+
+    use dasp_graph::{Buffer, NodeData, Processor};
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn voice(_: usize) -> SubtractiveVoice {
+        SubtractiveVoice::new(
+            DeriveOscillator::new(110.0, SAMPLE_RATE),
+            SvfModule::new(1200.0, 0.3, SAMPLE_RATE),
+            AdsrEnvelope::new(0.01, 0.1, 0.5, 0.1, SAMPLE_RATE),
+            VcaModule::new(Response::Linear, 0.0),
+            SAMPLE_RATE,
+        )
+    }
+
+    /// Renders a tenth of a second of a held note with the cutoff control set to `cutoff`,
+    /// returning the energy of the output.
+    fn energy_with_cutoff(cutoff: f32) -> f32 {
+        let mut graph = Graph::new();
+        let (voice, controls) = voice(0).build_graph_with_controls(&mut graph);
+        controls.cutoff.set(cutoff);
+
+        let gate = graph.add_node(NodeData::boxed1(Level::new(Clock::HIGH)));
+        graph.add_edge(gate, voice.gate_in().unwrap(), ());
+
+        let out = voice.audio_out().unwrap();
+        let mut processor = Processor::with_capacity(64);
+
+        (0..SAMPLE_RATE as usize / 10 / Buffer::LEN)
+            .map(|_| {
+                processor.process(&mut graph, out);
+                graph[out].buffers[0].iter().map(|s| s * s).sum::<f32>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn cutoff_control_changes_the_output() {
+        let open = energy_with_cutoff(0.0);
+        let closed = energy_with_cutoff(-4.0);
+
+        assert!(open > 0.0);
+        assert!(closed < open * 0.5, "closed {closed}, open {open}");
+    }
+
+    #[test]
+    fn poly_voices_forward_each_voice_controls() {
+        let mut graph = Graph::new();
+        let poly = PolyVoices::<SubtractiveVoice, 2>::new(StealPolicy::Oldest, voice);
+        let (_, controls) = poly.build_graph_with_controls(&mut graph);
+
+        for controls in controls.iter() {
+            assert_eq!(controls.cutoff.name(), "cutoff");
+            assert_eq!(controls.cutoff.range(), (-5.0, 5.0));
+        }
+    }
+}
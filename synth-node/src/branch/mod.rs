@@ -1,3 +1,3 @@
 mod sequential_switch;
 
-pub use sequential_switch::SequentialSwitch;
+pub use sequential_switch::{SequentialSwitch, SwitchMode};
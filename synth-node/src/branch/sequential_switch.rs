@@ -2,21 +2,97 @@ use crate::source::Clock;
 
 use dasp_graph::{Buffer, Input, Node};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwitchMode {
+    /// Input 0 is a clock, and each rising edge moves on to the next cycled input.
+    Clock,
+    /// Input 0 is an index CV, where `Clock::LOW` to `Clock::HIGH` is spread evenly across the
+    /// cycled inputs.
+    Index,
+}
+
+/// Routes one of its cycled inputs to every output buffer.
+///
+/// Input 0 is the clock or index CV, depending on the mode, and the cycled inputs follow it. With
+/// `with_reset`, input 1 is instead a reset, which returns to the first cycled input on each
+/// rising edge, and the cycled inputs start at input 2.
+///
+/// A clock or reset rises once it reaches the threshold, and can only rise again after falling
+/// below the threshold minus the hysteresis, so gates of any length advance by a single step.
 pub struct SequentialSwitch {
     cycled_inputs: usize,
-    current_input: usize,
+    current_step: usize,
+    mode: SwitchMode,
+    reset: bool,
+    threshold: f32,
+    hysteresis: f32,
+    clock_high: bool,
+    reset_high: bool,
 }
 
 impl SequentialSwitch {
     const CLOCK_INDEX: usize = 0;
-    const FIRST_INPUT_INDEX: usize = 1;
+    const RESET_INDEX: usize = 1;
 
     pub fn new(cycled_inputs: usize) -> Self {
         Self {
             cycled_inputs,
-            current_input: Self::FIRST_INPUT_INDEX,
+            current_step: 0,
+            mode: SwitchMode::Clock,
+            reset: false,
+            threshold: Clock::HIGH / 2.0,
+            hysteresis: 1.0,
+            clock_high: false,
+            reset_high: false,
         }
     }
+
+    pub fn with_mode(mut self, mode: SwitchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Takes a reset at input 1, moving the cycled inputs up by one.
+    pub fn with_reset(mut self) -> Self {
+        self.reset = true;
+        self
+    }
+
+    /// Defaults to a threshold of `Clock::HIGH / 2.0` with 1V of hysteresis.
+    pub fn with_threshold(mut self, threshold: f32, hysteresis: f32) -> Self {
+        self.threshold = threshold;
+        self.hysteresis = hysteresis.max(0.0);
+        self
+    }
+
+    /// Updates `high` from `sample`, returning whether it has just risen.
+    fn rising_edge(&self, high: &mut bool, sample: f32) -> bool {
+        if !*high && sample >= self.threshold {
+            *high = true;
+            return true;
+        }
+
+        if *high && sample < self.threshold - self.hysteresis {
+            *high = false;
+        }
+
+        false
+    }
+
+    fn first_input_index(&self) -> usize {
+        if self.reset {
+            Self::RESET_INDEX + 1
+        } else {
+            Self::CLOCK_INDEX + 1
+        }
+    }
+
+    fn index_step(&self, cv: f32) -> usize {
+        let position = (cv - Clock::LOW) / (Clock::HIGH - Clock::LOW);
+        let step = (position * self.cycled_inputs as f32).floor();
+
+        step.clamp(0.0, (self.cycled_inputs - 1) as f32) as usize
+    }
 }
 
 impl Node for SequentialSwitch {
@@ -35,31 +111,108 @@ impl Node for SequentialSwitch {
             return;
         }
 
-        let clock_buf = inputs
-            .get(Self::CLOCK_INDEX)
-            .and_then(|input| input.buffers().get(0))
-            .unwrap_or_else(|| &Buffer::SILENT);
+        let input_buf = |index: usize| {
+            inputs
+                .get(index)
+                .and_then(|input| input.buffers().first())
+                .unwrap_or(&Buffer::SILENT)
+        };
+
+        let clock_buf = input_buf(Self::CLOCK_INDEX);
+        let reset_buf = if self.reset {
+            input_buf(Self::RESET_INDEX)
+        } else {
+            &Buffer::SILENT
+        };
+        let first_input_index = self.first_input_index();
 
         for i in 0..Buffer::LEN {
-            let clock = clock_buf[i];
+            match self.mode {
+                SwitchMode::Clock => {
+                    let mut reset_high = self.reset_high;
+                    let mut clock_high = self.clock_high;
 
-            if clock >= Clock::HIGH {
-                self.current_input += 1;
+                    let reset = self.rising_edge(&mut reset_high, reset_buf[i]);
+                    let clock = self.rising_edge(&mut clock_high, clock_buf[i]);
 
-                if self.current_input > self.cycled_inputs {
-                    self.current_input = Self::FIRST_INPUT_INDEX;
+                    if reset {
+                        self.current_step = 0;
+                    } else if clock {
+                        self.current_step = (self.current_step + 1) % self.cycled_inputs;
+                    }
+
+                    self.reset_high = reset_high;
+                    self.clock_high = clock_high;
+                }
+                SwitchMode::Index => {
+                    self.current_step = self.index_step(clock_buf[i]);
                 }
             }
 
-            let sample = inputs
-                .get(self.current_input)
-                .and_then(|input| input.buffers().get(0))
-                .and_then(|input_buf| input_buf.get(i))
-                .unwrap_or_else(|| &0.0);
+            let sample = input_buf(first_input_index + self.current_step)[i];
 
             for buffer in output.iter_mut() {
-                buffer[i] = *sample;
+                buffer[i] = sample;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::process_patched;
+
+    /// A buffer holding `value` on every sample.
+    fn constant(value: f32) -> Buffer {
+        let mut buffer = Buffer::SILENT;
+        buffer.iter_mut().for_each(|sample| *sample = value);
+        buffer
+    }
+
+    #[test]
+    fn clock_advances_once_per_rising_edge() {
+        let mut switch = SequentialSwitch::new(3);
+        let levels = [constant(1.0), constant(2.0), constant(3.0)];
+
+        // A long gate with some noise around the threshold still rises once.
+        let mut clock = Buffer::SILENT;
+        for (i, sample) in clock.iter_mut().enumerate().skip(10).take(20) {
+            *sample = if i % 2 == 0 {
+                Clock::HIGH
+            } else {
+                Clock::HIGH / 2.0 - 0.5
+            };
+        }
+
+        let patched = |clock: &Buffer| [&[clock.clone()][..], &levels[..]].concat();
+
+        let output = process_patched(&mut switch, &patched(&clock), 1);
+        assert_eq!(output[0][0], 1.0);
+        assert_eq!(output[0][Buffer::LEN - 1], 2.0);
+
+        let output = process_patched(&mut switch, &patched(&Buffer::SILENT), 1);
+        assert!(output[0].iter().all(|&sample| sample == 2.0));
+
+        for expected in [3.0, 1.0, 2.0] {
+            let output = process_patched(&mut switch, &patched(&clock), 1);
+            assert_eq!(output[0][Buffer::LEN - 1], expected);
+        }
+    }
+
+    #[test]
+    fn reset_returns_to_the_first_input() {
+        let mut switch = SequentialSwitch::new(2).with_reset();
+
+        let mut clock = Buffer::SILENT;
+        clock[0] = Clock::HIGH;
+        let mut reset = Buffer::SILENT;
+        reset[10] = Clock::HIGH;
+
+        let inputs = [clock, reset, constant(1.0), constant(2.0)];
+        let output = process_patched(&mut switch, &inputs, 1);
+
+        assert_eq!(output[0][0], 2.0);
+        assert_eq!(output[0][10], 1.0);
+    }
+}
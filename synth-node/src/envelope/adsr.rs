@@ -0,0 +1,273 @@
+use crate::source::Clock;
+
+use dasp_graph::{Buffer, Input, Node};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    Linear,
+    /// One-pole segments, with the attack aiming past full scale like an analogue envelope.
+    Exponential,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Attack/decay/sustain/release envelope running between 0 and 1.
+///
+/// Input 0 is the gate, which opens once it reaches `Clock::HIGH` and closes once it falls back to
+/// `Clock::LOW`. With `with_trigger`, each rising edge instead opens the gate for a fixed time, so
+/// `Clock`'s one-sample pulses can play the whole envelope. Inputs 1 to 4 optionally replace the attack, decay, sustain and release
+/// parameters. Times are in seconds, and for linear curves they are the time taken to cover the
+/// full 0 to 1 range. For exponential curves the decay and release times are the time taken to
+/// get within 60dB of the target.
+pub struct Adsr {
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+    curve: Curve,
+    sample_rate: f32,
+    stage: Stage,
+    gate: bool,
+    gate_time: Option<f32>,
+    hold: u32,
+    level: f32,
+}
+
+impl Adsr {
+    const GATE_INDEX: usize = 0;
+    const ATTACK_INDEX: usize = 1;
+    const DECAY_INDEX: usize = 2;
+    const SUSTAIN_INDEX: usize = 3;
+    const RELEASE_INDEX: usize = 4;
+
+    const ATTACK_TARGET: f32 = 1.3;
+    const SILENCE: f32 = 0.001;
+
+    pub fn new(attack: f32, decay: f32, sustain: f32, release: f32, sample_rate: u32) -> Self {
+        Self {
+            attack,
+            decay,
+            sustain,
+            release,
+            curve: Curve::Linear,
+            sample_rate: sample_rate as f32,
+            stage: Stage::Idle,
+            gate: false,
+            gate_time: None,
+            hold: 0,
+            level: 0.0,
+        }
+    }
+
+    pub fn with_curve(mut self, curve: Curve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Holds the envelope open for `gate_time` seconds after each rising edge of the gate,
+    /// however long the gate itself stays high. A new edge restarts the attack and the hold.
+    pub fn with_trigger(mut self, gate_time: f32) -> Self {
+        self.gate_time = Some(gate_time.max(0.0));
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    fn update_gate(&mut self, gate: f32) {
+        let rising = !self.gate && gate >= Clock::HIGH;
+        let falling = self.gate && gate <= Clock::LOW;
+
+        if rising {
+            self.gate = true;
+        } else if falling {
+            self.gate = false;
+        }
+
+        match self.gate_time {
+            None => {
+                if rising {
+                    self.stage = Stage::Attack;
+                } else if falling {
+                    self.stage = Stage::Release;
+                }
+            }
+            Some(gate_time) => {
+                if rising {
+                    self.stage = Stage::Attack;
+                    self.hold = (gate_time * self.sample_rate).round() as u32;
+                    return;
+                }
+
+                self.hold = self.hold.saturating_sub(1);
+
+                let open = matches!(self.stage, Stage::Attack | Stage::Decay | Stage::Sustain);
+
+                if self.hold == 0 && open {
+                    self.stage = Stage::Release;
+                }
+            }
+        }
+    }
+
+    /// Moves `level` one sample towards `target` over a segment lasting `time` seconds, returning
+    /// whether the target has been reached.
+    fn approach(&mut self, target: f32, time: f32, time_constants: f32) -> bool {
+        let samples = time * self.sample_rate;
+
+        if samples <= 1.0 {
+            self.level = target;
+            return true;
+        }
+
+        match self.curve {
+            Curve::Linear => {
+                let step = 1.0 / samples;
+
+                if self.level < target {
+                    self.level = (self.level + step).min(target);
+                } else {
+                    self.level = (self.level - step).max(target);
+                }
+
+                self.level == target
+            }
+            Curve::Exponential => {
+                let coeff = 1.0 - (-time_constants / samples).exp();
+                self.level += (target - self.level) * coeff;
+                (self.level - target).abs() < Self::SILENCE
+            }
+        }
+    }
+
+    fn get_sample(
+        &mut self,
+        gate: f32,
+        attack: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+    ) -> f32 {
+        self.update_gate(gate);
+
+        let sustain = sustain.clamp(0.0, 1.0);
+
+        match self.stage {
+            Stage::Idle => {}
+            Stage::Attack => {
+                let reached = match self.curve {
+                    Curve::Linear => self.approach(1.0, attack, 0.0),
+                    Curve::Exponential => {
+                        let target = Self::ATTACK_TARGET;
+                        let time_constants = (target / (target - 1.0)).ln();
+                        self.approach(target, attack, time_constants);
+                        self.level >= 1.0
+                    }
+                };
+
+                if reached {
+                    self.level = 1.0;
+                    self.stage = Stage::Decay;
+                }
+            }
+            Stage::Decay => {
+                if self.approach(sustain, decay, Self::SILENCE.recip().ln()) {
+                    self.level = sustain;
+                    self.stage = Stage::Sustain;
+                }
+            }
+            Stage::Sustain => {
+                self.level = sustain;
+            }
+            Stage::Release => {
+                if self.approach(0.0, release, Self::SILENCE.recip().ln()) {
+                    self.level = 0.0;
+                    self.stage = Stage::Idle;
+                }
+            }
+        }
+
+        self.level
+    }
+}
+
+impl Node for Adsr {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let input_sample =
+            |index: usize, i: usize| inputs.get(index).map(|input: &Input| input.buffers()[0][i]);
+
+        for i in 0..Buffer::LEN {
+            let gate = input_sample(Self::GATE_INDEX, i).unwrap_or(Clock::LOW);
+            let attack = input_sample(Self::ATTACK_INDEX, i).unwrap_or(self.attack);
+            let decay = input_sample(Self::DECAY_INDEX, i).unwrap_or(self.decay);
+            let sustain = input_sample(Self::SUSTAIN_INDEX, i).unwrap_or(self.sustain);
+            let release = input_sample(Self::RELEASE_INDEX, i).unwrap_or(self.release);
+
+            let sample = self.get_sample(gate, attack, decay, sustain, release);
+
+            for buffer in output.iter_mut() {
+                buffer[i] = sample;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::process_patched;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// Drives `adsr` from a 120bpm `Clock` for two seconds, returning every sample.
+    fn clocked(mut adsr: Adsr) -> Vec<f32> {
+        let mut clock = Clock::new(120.0, SAMPLE_RATE);
+        let mut pulses = [Buffer::SILENT];
+        let mut samples = Vec::new();
+
+        for _ in 0..(2 * SAMPLE_RATE as usize / Buffer::LEN) {
+            clock.process(&[], &mut pulses);
+            let output = process_patched(&mut adsr, &pulses, 1);
+            samples.extend_from_slice(&output[0]);
+        }
+
+        samples
+    }
+
+    #[test]
+    fn clock_pulse_only_opens_a_gate_for_one_sample() {
+        let samples = clocked(Adsr::new(0.01, 0.05, 0.6, 0.1, SAMPLE_RATE));
+
+        assert!(samples.iter().all(|&level| level < 0.01));
+    }
+
+    #[test]
+    fn trigger_from_clock_reaches_sustain() {
+        let adsr = Adsr::new(0.01, 0.05, 0.6, 0.1, SAMPLE_RATE).with_trigger(0.2);
+        let samples = clocked(adsr);
+
+        let peak = samples.iter().copied().fold(0.0, f32::max);
+        assert_eq!(peak, 1.0);
+        assert!(samples.contains(&0.6));
+
+        // The first pulse lands at 0.5s and the next at 1s, so the hold and release are over
+        // in between.
+        let sample = |seconds: f32| (seconds * SAMPLE_RATE as f32) as usize;
+        let between = sample(0.9)..sample(0.95);
+        assert!(samples[between].iter().all(|&level| level == 0.0));
+    }
+}
@@ -0,0 +1,3 @@
+mod adsr;
+
+pub use adsr::{Adsr, Curve};
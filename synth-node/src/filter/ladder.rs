@@ -0,0 +1,95 @@
+use dasp_graph::{Buffer, Input, Node};
+
+/// Four-pole lowpass ladder filter in the style of the Moog transistor ladder.
+///
+/// Input 0 is the audio, input 1 is an optional cutoff CV following the `freq * 2^v_oct`
+/// convention, and input 2 optionally replaces the resonance parameter. Each stage saturates
+/// through `tanh`, and `drive` sets the gain into the first stage. Resonance runs from 0 to 1, and
+/// the filter self-oscillates at its cutoff frequency from around 0.9.
+pub struct LadderFilter {
+    cutoff: f32,
+    resonance: f32,
+    drive: f32,
+    sample_rate: f32,
+    stages: [f32; 4],
+}
+
+impl LadderFilter {
+    const AUDIO_INDEX: usize = 0;
+    const CUTOFF_INDEX: usize = 1;
+    const RESONANCE_INDEX: usize = 2;
+
+    const MAX_FEEDBACK: f32 = 4.5;
+
+    pub fn new(cutoff: f32, resonance: f32, sample_rate: u32) -> Self {
+        Self {
+            cutoff,
+            resonance,
+            drive: 1.0,
+            sample_rate: sample_rate as f32,
+            stages: [0.0; 4],
+        }
+    }
+
+    pub fn with_drive(mut self, drive: f32) -> Self {
+        self.drive = drive;
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    pub fn get_sample(&mut self, input: f32, v_oct: Option<f32>, resonance: f32) -> f32 {
+        let nyquist = self.sample_rate / 2.0;
+        let cutoff =
+            (self.cutoff * 2_f32.powf(v_oct.unwrap_or_default())).clamp(1.0, nyquist * 0.9);
+
+        // Zero-delay feedback: solve the linear ladder for its input, then saturate each stage.
+        let g = (std::f32::consts::PI * cutoff / self.sample_rate).tan();
+        let g = g / (1.0 + g);
+
+        let [s1, s2, s3, s4] = self.stages;
+        let state = (1.0 - g) * (g * g * g * s1 + g * g * s2 + g * s3 + s4);
+
+        let feedback = resonance.clamp(0.0, 1.0) * Self::MAX_FEEDBACK;
+        let u = (input * self.drive - feedback * state) / (1.0 + feedback * g * g * g * g);
+
+        let mut x = u.tanh();
+        let mut y = 0.0;
+
+        for stage in self.stages.iter_mut() {
+            let v = g * (x - *stage);
+            y = v + *stage;
+            *stage = y + v;
+            x = y.tanh();
+        }
+
+        y
+    }
+}
+
+impl Node for LadderFilter {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let input_sample =
+            |index: usize, i: usize| inputs.get(index).map(|input: &Input| input.buffers()[0][i]);
+
+        for i in 0..Buffer::LEN {
+            let audio = input_sample(Self::AUDIO_INDEX, i).unwrap_or_default();
+            let v_oct = input_sample(Self::CUTOFF_INDEX, i);
+            let resonance = input_sample(Self::RESONANCE_INDEX, i).unwrap_or(self.resonance);
+
+            let sample = self.get_sample(audio, v_oct, resonance);
+
+            for buffer in output.iter_mut() {
+                buffer[i] = sample;
+            }
+        }
+    }
+}
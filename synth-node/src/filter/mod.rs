@@ -0,0 +1,5 @@
+mod ladder;
+mod svf;
+
+pub use ladder::LadderFilter;
+pub use svf::{FilterResponse, StateVariableFilter};
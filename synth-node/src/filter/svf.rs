@@ -0,0 +1,120 @@
+use dasp_graph::{Buffer, Input, Node};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterResponse {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Notch,
+}
+
+impl FilterResponse {
+    pub const ALL: [FilterResponse; 4] = [
+        FilterResponse::Lowpass,
+        FilterResponse::Highpass,
+        FilterResponse::Bandpass,
+        FilterResponse::Notch,
+    ];
+}
+
+/// Topology-preserving transform state-variable filter.
+///
+/// Input 0 is the audio, input 1 is an optional cutoff CV following the `freq * 2^v_oct`
+/// convention, and input 2 optionally replaces the resonance parameter. Resonance runs from 0 to
+/// 1, where the filter stops damping altogether.
+///
+/// All four responses are computed together. Output buffer `N` receives response `N` of those
+/// given to `with_responses`, which by default is `FilterResponse::ALL`.
+pub struct StateVariableFilter {
+    cutoff: f32,
+    resonance: f32,
+    sample_rate: f32,
+    responses: Vec<FilterResponse>,
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl StateVariableFilter {
+    /// Output buffers of the responses, while the filter has the default `FilterResponse::ALL`.
+    pub const LOWPASS_BUFFER: usize = 0;
+    pub const HIGHPASS_BUFFER: usize = 1;
+    pub const BANDPASS_BUFFER: usize = 2;
+    pub const NOTCH_BUFFER: usize = 3;
+
+    const AUDIO_INDEX: usize = 0;
+    const CUTOFF_INDEX: usize = 1;
+    const RESONANCE_INDEX: usize = 2;
+
+    pub fn new(cutoff: f32, resonance: f32, sample_rate: u32) -> Self {
+        Self {
+            cutoff,
+            resonance,
+            sample_rate: sample_rate as f32,
+            responses: FilterResponse::ALL.to_vec(),
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        }
+    }
+
+    pub fn with_responses(mut self, responses: &[FilterResponse]) -> Self {
+        self.responses = responses.to_vec();
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    /// Filters one sample, returning the lowpass, highpass, bandpass and notch responses.
+    pub fn get_sample(&mut self, input: f32, v_oct: Option<f32>, resonance: f32) -> [f32; 4] {
+        let nyquist = self.sample_rate / 2.0;
+        let cutoff =
+            (self.cutoff * 2_f32.powf(v_oct.unwrap_or_default())).clamp(1.0, nyquist * 0.98);
+
+        let g = (std::f32::consts::PI * cutoff / self.sample_rate).tan();
+        let k = 2.0 - 2.0 * resonance.clamp(0.0, 1.0);
+
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        let v3 = input - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        let lowpass = v2;
+        let bandpass = v1;
+        let highpass = input - k * v1 - v2;
+        let notch = lowpass + highpass;
+
+        [lowpass, highpass, bandpass, notch]
+    }
+}
+
+impl Node for StateVariableFilter {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let input_sample =
+            |index: usize, i: usize| inputs.get(index).map(|input: &Input| input.buffers()[0][i]);
+
+        for i in 0..Buffer::LEN {
+            let audio = input_sample(Self::AUDIO_INDEX, i).unwrap_or_default();
+            let v_oct = input_sample(Self::CUTOFF_INDEX, i);
+            let resonance = input_sample(Self::RESONANCE_INDEX, i).unwrap_or(self.resonance);
+
+            let samples = self.get_sample(audio, v_oct, resonance);
+
+            for (buffer, response) in output.iter_mut().zip(&self.responses) {
+                buffer[i] = samples[*response as usize];
+            }
+        }
+    }
+}
@@ -1,5 +1,10 @@
 pub mod branch;
+pub mod envelope;
+pub mod filter;
+pub mod midi;
 pub mod ops;
+pub mod sequencer;
 pub mod sink;
 pub mod source;
 pub mod util;
+pub mod voice;
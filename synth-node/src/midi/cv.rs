@@ -0,0 +1,192 @@
+use super::{MidiEvent, MidiTransport, MonoNotes};
+use crate::source::LevelCommand;
+
+use dasp_graph::{Buffer, Input, Node};
+
+use std::sync::mpsc::Sender;
+
+struct CcMapping {
+    controller: u8,
+    min: f32,
+    max: f32,
+    tx: Sender<LevelCommand>,
+}
+
+/// Turns live MIDI from a transport into control voltages for a monophonic voice.
+///
+/// Output buffer 0 holds the pitch in volts per octave relative to the reference note, buffer 1 a
+/// gate at `Clock::HIGH` while a note is held, buffer 2 the velocity between 0 and 1, buffer 3 the
+/// pitch bend in volts per octave, and buffer 4 the mod wheel between 0 and 1. Overlapping notes use
+/// last-note priority.
+///
+/// Events are read at the start of each block, so timing is only as fine as `Buffer::LEN`.
+pub struct MidiCv<T: MidiTransport> {
+    transport: T,
+    channel: Option<u8>,
+    notes: MonoNotes,
+    bend_range: f32,
+    pitch_bend: f32,
+    mod_wheel: f32,
+    cc_mappings: Vec<CcMapping>,
+}
+
+impl<T: MidiTransport> MidiCv<T> {
+    pub const V_OCT_BUFFER: usize = 0;
+    pub const GATE_BUFFER: usize = 1;
+    pub const VELOCITY_BUFFER: usize = 2;
+    pub const PITCH_BEND_BUFFER: usize = 3;
+    pub const MOD_WHEEL_BUFFER: usize = 4;
+    pub const BUFFERS: usize = 5;
+
+    const MOD_WHEEL_CC: u8 = 1;
+    const ALL_NOTES_OFF_CC: u8 = 123;
+
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            channel: None,
+            notes: MonoNotes::new(60),
+            bend_range: 2.0,
+            pitch_bend: 0.0,
+            mod_wheel: 0.0,
+            cc_mappings: Vec::new(),
+        }
+    }
+
+    /// Only responds to `channel`, counted from 0. Every channel is heard by default.
+    pub fn with_midi_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Sets the MIDI note that is played at 0V, which defaults to middle C.
+    pub fn with_reference_note(mut self, note: u8) -> Self {
+        self.notes.set_reference_note(note);
+        self
+    }
+
+    /// Sets how far a full pitch bend goes, in semitones. Defaults to 2.
+    pub fn with_bend_range(mut self, semitones: f32) -> Self {
+        self.bend_range = semitones;
+        self
+    }
+
+    /// Sends each value of `controller` to a `Level` as a `SetLevel` scaled from `min` to `max`.
+    pub fn with_cc(mut self, controller: u8, min: f32, max: f32, tx: Sender<LevelCommand>) -> Self {
+        self.cc_mappings.push(CcMapping {
+            controller,
+            min,
+            max,
+            tx,
+        });
+        self
+    }
+
+    fn process_events(&mut self) {
+        while let Some(event) = self.transport.try_recv() {
+            if self
+                .channel
+                .is_some_and(|channel| channel != event.channel())
+            {
+                continue;
+            }
+
+            match event {
+                MidiEvent::NoteOn { note, velocity, .. } => {
+                    self.notes.note_on(note, velocity as f32 / 127.0);
+                }
+                MidiEvent::NoteOff { note, .. } => {
+                    self.notes.note_off(note);
+                }
+                MidiEvent::PitchBend { bend, .. } => {
+                    self.pitch_bend = bend * self.bend_range / 12.0;
+                }
+                MidiEvent::ControlChange {
+                    controller, value, ..
+                } => self.control_change(controller, value),
+            }
+        }
+    }
+
+    fn control_change(&mut self, controller: u8, value: u8) {
+        let unit = value as f32 / 127.0;
+
+        match controller {
+            Self::MOD_WHEEL_CC => self.mod_wheel = unit,
+            Self::ALL_NOTES_OFF_CC => self.notes.all_notes_off(),
+            _ => {}
+        }
+
+        for mapping in self.cc_mappings.iter() {
+            if mapping.controller == controller {
+                let level = mapping.min + unit * (mapping.max - mapping.min);
+                let _ = mapping.tx.send(LevelCommand::SetLevel(level));
+            }
+        }
+    }
+}
+
+impl<T: MidiTransport> Node for MidiCv<T> {
+    fn process(&mut self, _inputs: &[Input], output: &mut [Buffer]) {
+        self.process_events();
+
+        for i in 0..Buffer::LEN {
+            let gate = self.notes.next_gate();
+
+            for (index, buffer) in output.iter_mut().enumerate() {
+                buffer[i] = match index {
+                    Self::V_OCT_BUFFER => self.notes.v_oct(),
+                    Self::GATE_BUFFER => gate,
+                    Self::VELOCITY_BUFFER => self.notes.velocity(),
+                    Self::PITCH_BEND_BUFFER => self.pitch_bend,
+                    Self::MOD_WHEEL_BUFFER => self.mod_wheel,
+                    _ => 0.0,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{midi::VirtualMidiInput, source::Clock};
+
+    #[test]
+    fn virtual_input_drives_gate_and_v_oct() {
+        let (transport, tx) = VirtualMidiInput::new();
+        let mut midi_cv = MidiCv::new(transport);
+        let mut output = vec![Buffer::SILENT; MidiCv::<VirtualMidiInput>::BUFFERS];
+
+        midi_cv.process(&[], &mut output);
+        assert!(output[MidiCv::<VirtualMidiInput>::GATE_BUFFER]
+            .iter()
+            .all(|&gate| gate == Clock::LOW));
+
+        tx.send(MidiEvent::NoteOn {
+            channel: 0,
+            note: 72,
+            velocity: 100,
+        })
+        .unwrap();
+        midi_cv.process(&[], &mut output);
+
+        assert!(output[MidiCv::<VirtualMidiInput>::GATE_BUFFER]
+            .iter()
+            .all(|&gate| gate == Clock::HIGH));
+        assert!(output[MidiCv::<VirtualMidiInput>::V_OCT_BUFFER]
+            .iter()
+            .all(|&v_oct| (v_oct - 1.0).abs() < 1e-6));
+
+        tx.send(MidiEvent::NoteOff {
+            channel: 0,
+            note: 72,
+        })
+        .unwrap();
+        midi_cv.process(&[], &mut output);
+
+        assert!(output[MidiCv::<VirtualMidiInput>::GATE_BUFFER]
+            .iter()
+            .all(|&gate| gate == Clock::LOW));
+    }
+}
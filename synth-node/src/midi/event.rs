@@ -0,0 +1,71 @@
+use midly::{live::LiveEvent, MidiMessage};
+
+/// The channel messages MIDI-to-CV conversion responds to. Channels are counted from 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiEvent {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    /// Bend between -1 and 1.
+    PitchBend {
+        channel: u8,
+        bend: f32,
+    },
+}
+
+impl MidiEvent {
+    /// Parses a single raw MIDI message, returning `None` for anything that isn't one of the
+    /// supported channel messages. A note on with zero velocity is read as a note off.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let (channel, message) = match LiveEvent::parse(bytes).ok()? {
+            LiveEvent::Midi { channel, message } => (channel.as_int(), message),
+            _ => return None,
+        };
+
+        let event = match message {
+            MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => MidiEvent::NoteOn {
+                channel,
+                note: key.as_int(),
+                velocity: vel.as_int(),
+            },
+            MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                MidiEvent::NoteOff {
+                    channel,
+                    note: key.as_int(),
+                }
+            }
+            MidiMessage::Controller { controller, value } => MidiEvent::ControlChange {
+                channel,
+                controller: controller.as_int(),
+                value: value.as_int(),
+            },
+            MidiMessage::PitchBend { bend } => MidiEvent::PitchBend {
+                channel,
+                bend: bend.as_f32(),
+            },
+            _ => return None,
+        };
+
+        Some(event)
+    }
+
+    pub fn channel(&self) -> u8 {
+        match *self {
+            MidiEvent::NoteOn { channel, .. }
+            | MidiEvent::NoteOff { channel, .. }
+            | MidiEvent::ControlChange { channel, .. }
+            | MidiEvent::PitchBend { channel, .. } => channel,
+        }
+    }
+}
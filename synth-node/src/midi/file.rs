@@ -0,0 +1,229 @@
+use super::MonoNotes;
+
+use dasp_graph::{Buffer, Input, Node};
+use midly::{MetaMessage, MidiMessage, Smf, Timing, TrackEventKind};
+
+use std::{error, fmt, fs, io, path::Path, time::Duration};
+
+#[derive(Debug)]
+pub enum MidiFileError {
+    Io(io::Error),
+    Parse(midly::Error),
+}
+
+impl fmt::Display for MidiFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidiFileError::Io(e) => write!(f, "failed to read MIDI file: {}", e),
+            MidiFileError::Parse(e) => write!(f, "failed to parse MIDI file: {}", e),
+        }
+    }
+}
+
+impl error::Error for MidiFileError {}
+
+impl From<io::Error> for MidiFileError {
+    fn from(e: io::Error) -> Self {
+        MidiFileError::Io(e)
+    }
+}
+
+impl From<midly::Error> for MidiFileError {
+    fn from(e: midly::Error) -> Self {
+        MidiFileError::Parse(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NoteKind {
+    On { note: u8, velocity: f32 },
+    Off { note: u8 },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct NoteEvent {
+    seconds: f64,
+    track: usize,
+    channel: u8,
+    kind: NoteKind,
+}
+
+/// Plays the notes of a Standard MIDI File as a monophonic voice.
+///
+/// Output buffer 0 holds the pitch in volts per octave relative to the reference note, buffer 1 a
+/// gate at `Clock::HIGH` while a note is held, and buffer 2 the velocity between 0 and 1.
+/// Overlapping notes use last-note priority. Every track and channel is played unless narrowed down
+/// with `with_track` or `with_midi_channel`.
+///
+/// Note times are taken from the file's tempo map and land on the nearest sample.
+pub struct MidiFilePlayer {
+    events: Vec<NoteEvent>,
+    next_event: usize,
+    track: Option<usize>,
+    channel: Option<u8>,
+    notes: MonoNotes,
+    sample_rate: f64,
+    position: u64,
+}
+
+impl MidiFilePlayer {
+    pub const V_OCT_BUFFER: usize = 0;
+    pub const GATE_BUFFER: usize = 1;
+    pub const VELOCITY_BUFFER: usize = 2;
+
+    /// Tempo assumed until the first tempo event, in microseconds per beat.
+    const DEFAULT_TEMPO: u32 = 500_000;
+
+    pub fn open<P: AsRef<Path>>(path: P, sample_rate: u32) -> Result<Self, MidiFileError> {
+        Self::from_bytes(&fs::read(path)?, sample_rate)
+    }
+
+    pub fn from_bytes(bytes: &[u8], sample_rate: u32) -> Result<Self, MidiFileError> {
+        let smf = Smf::parse(bytes)?;
+
+        Ok(Self {
+            events: Self::note_events(&smf),
+            next_event: 0,
+            track: None,
+            channel: None,
+            notes: MonoNotes::new(60),
+            sample_rate: sample_rate as f64,
+            position: 0,
+        })
+    }
+
+    pub fn with_track(mut self, track: usize) -> Self {
+        self.track = Some(track);
+        self
+    }
+
+    /// Only plays notes on `channel`, counted from 0.
+    pub fn with_midi_channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Sets the MIDI note that is played at 0V, which defaults to middle C.
+    pub fn with_reference_note(mut self, note: u8) -> Self {
+        self.notes.set_reference_note(note);
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f64;
+    }
+
+    /// Time of the last note event in the file.
+    pub fn duration(&self) -> Duration {
+        let seconds = self.events.last().map(|event| event.seconds).unwrap_or(0.0);
+        Duration::from_secs_f64(seconds)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_event >= self.events.len()
+    }
+
+    /// Flattens every track into note events ordered by time, following the tempo changes
+    /// found on any track.
+    fn note_events(smf: &Smf) -> Vec<NoteEvent> {
+        let mut timeline = Vec::new();
+
+        for (track, events) in smf.tracks.iter().enumerate() {
+            let mut ticks = 0_u64;
+
+            for event in events {
+                ticks += event.delta.as_int() as u64;
+                timeline.push((ticks, track, event.kind));
+            }
+        }
+
+        // The sort is stable, so events on the same tick keep their order within each track.
+        timeline.sort_by_key(|(ticks, _, _)| *ticks);
+
+        let mut events = Vec::new();
+        let mut tempo = Self::DEFAULT_TEMPO;
+        let mut seconds = 0.0;
+        let mut last_ticks = 0;
+
+        for (ticks, track, kind) in timeline {
+            let seconds_per_tick = match smf.header.timing {
+                Timing::Metrical(ticks_per_beat) => {
+                    tempo as f64 / 1_000_000.0 / ticks_per_beat.as_int() as f64
+                }
+                Timing::Timecode(fps, subframes) => 1.0 / fps.as_f32() as f64 / subframes as f64,
+            };
+
+            seconds += (ticks - last_ticks) as f64 * seconds_per_tick;
+            last_ticks = ticks;
+
+            let (channel, message) = match kind {
+                TrackEventKind::Meta(MetaMessage::Tempo(new_tempo)) => {
+                    tempo = new_tempo.as_int();
+                    continue;
+                }
+                TrackEventKind::Midi { channel, message } => (channel.as_int(), message),
+                _ => continue,
+            };
+
+            let kind = match message {
+                MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => NoteKind::On {
+                    note: key.as_int(),
+                    velocity: vel.as_int() as f32 / 127.0,
+                },
+                MidiMessage::NoteOn { key, .. } | MidiMessage::NoteOff { key, .. } => {
+                    NoteKind::Off { note: key.as_int() }
+                }
+                _ => continue,
+            };
+
+            events.push(NoteEvent {
+                seconds,
+                track,
+                channel,
+                kind,
+            });
+        }
+
+        events
+    }
+
+    fn plays(&self, event: &NoteEvent) -> bool {
+        self.track.is_none_or(|track| track == event.track)
+            && self.channel.is_none_or(|channel| channel == event.channel)
+    }
+}
+
+impl Node for MidiFilePlayer {
+    fn process(&mut self, _inputs: &[Input], output: &mut [Buffer]) {
+        for i in 0..Buffer::LEN {
+            while let Some(event) = self.events.get(self.next_event).copied() {
+                let sample = (event.seconds * self.sample_rate).round() as u64;
+
+                if sample > self.position {
+                    break;
+                }
+
+                if self.plays(&event) {
+                    match event.kind {
+                        NoteKind::On { note, velocity } => self.notes.note_on(note, velocity),
+                        NoteKind::Off { note } => self.notes.note_off(note),
+                    }
+                }
+
+                self.next_event += 1;
+            }
+
+            self.position += 1;
+
+            let gate = self.notes.next_gate();
+
+            for (index, buffer) in output.iter_mut().enumerate() {
+                buffer[i] = match index {
+                    Self::V_OCT_BUFFER => self.notes.v_oct(),
+                    Self::GATE_BUFFER => gate,
+                    _ => self.notes.velocity(),
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+mod cv;
+mod event;
+mod file;
+mod mono;
+mod transport;
+
+pub use cv::MidiCv;
+pub use event::MidiEvent;
+pub use file::{MidiFileError, MidiFilePlayer};
+pub(crate) use mono::MonoNotes;
+pub use transport::{MidiTransport, VirtualMidiInput};
+#[cfg(feature = "midir")]
+pub use transport::{MidirError, MidirInput};
+
+/// Pitch of a MIDI note in volts per octave, with `reference_note` at 0V.
+pub fn note_to_v_oct(note: u8, reference_note: u8) -> f32 {
+    (note as f32 - reference_note as f32) / 12.0
+}
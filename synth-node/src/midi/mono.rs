@@ -0,0 +1,76 @@
+use super::note_to_v_oct;
+use crate::source::Clock;
+
+/// Last-note priority tracking of held MIDI notes for a monophonic voice.
+///
+/// Releasing the newest note falls back to the pitch of the one held before it without
+/// retriggering, while a new note that starts while the gate is high drops it for a single sample.
+#[derive(Clone, Debug)]
+pub(crate) struct MonoNotes {
+    held: Vec<u8>,
+    reference_note: u8,
+    v_oct: f32,
+    velocity: f32,
+    retrigger: bool,
+    gate_high: bool,
+}
+
+impl MonoNotes {
+    pub(crate) fn new(reference_note: u8) -> Self {
+        Self {
+            held: Vec::new(),
+            reference_note,
+            v_oct: 0.0,
+            velocity: 0.0,
+            retrigger: false,
+            gate_high: false,
+        }
+    }
+
+    pub(crate) fn set_reference_note(&mut self, reference_note: u8) {
+        self.reference_note = reference_note;
+    }
+
+    pub(crate) fn note_on(&mut self, note: u8, velocity: f32) {
+        self.retrigger = true;
+        self.held.retain(|held| *held != note);
+        self.held.push(note);
+        self.v_oct = note_to_v_oct(note, self.reference_note);
+        self.velocity = velocity;
+    }
+
+    pub(crate) fn note_off(&mut self, note: u8) {
+        self.held.retain(|held| *held != note);
+
+        if let Some(last) = self.held.last() {
+            self.v_oct = note_to_v_oct(*last, self.reference_note);
+        }
+    }
+
+    pub(crate) fn all_notes_off(&mut self) {
+        self.held.clear();
+    }
+
+    pub(crate) fn v_oct(&self) -> f32 {
+        self.v_oct
+    }
+
+    pub(crate) fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// The gate for the next sample.
+    pub(crate) fn next_gate(&mut self) -> f32 {
+        let retrigger = self.retrigger && self.gate_high;
+        let gate_high = !self.held.is_empty() && !retrigger;
+
+        self.retrigger = false;
+        self.gate_high = gate_high;
+
+        if gate_high {
+            Clock::HIGH
+        } else {
+            Clock::LOW
+        }
+    }
+}
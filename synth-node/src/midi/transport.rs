@@ -0,0 +1,130 @@
+use super::MidiEvent;
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// A source of live MIDI events, polled by `MidiCv` once per block.
+pub trait MidiTransport {
+    /// Returns the next pending event without blocking.
+    fn try_recv(&mut self) -> Option<MidiEvent>;
+}
+
+/// In-memory MIDI input fed through a channel, for tests and machines without MIDI hardware.
+pub struct VirtualMidiInput {
+    rx: Receiver<MidiEvent>,
+}
+
+impl VirtualMidiInput {
+    pub fn new() -> (Self, Sender<MidiEvent>) {
+        let (tx, rx) = mpsc::channel();
+        (Self { rx }, tx)
+    }
+}
+
+impl MidiTransport for VirtualMidiInput {
+    fn try_recv(&mut self) -> Option<MidiEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+#[cfg(feature = "midir")]
+pub use self::midir_input::{MidirError, MidirInput};
+
+#[cfg(feature = "midir")]
+mod midir_input {
+    use super::{MidiEvent, MidiTransport};
+
+    use midir::{ConnectErrorKind, InitError, MidiInput, MidiInputConnection};
+
+    use std::{
+        error, fmt,
+        sync::mpsc::{self, Receiver},
+    };
+
+    #[derive(Debug)]
+    pub enum MidirError {
+        Init(InitError),
+        NoSuchPort(usize),
+        Connect(ConnectErrorKind),
+    }
+
+    impl fmt::Display for MidirError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                MidirError::Init(e) => write!(f, "failed to open MIDI input: {}", e),
+                MidirError::NoSuchPort(port) => write!(f, "no MIDI input port {}", port),
+                MidirError::Connect(e) => write!(f, "failed to connect MIDI input: {}", e),
+            }
+        }
+    }
+
+    impl error::Error for MidirError {}
+
+    impl From<InitError> for MidirError {
+        fn from(e: InitError) -> Self {
+            MidirError::Init(e)
+        }
+    }
+
+    /// Hardware MIDI input through `midir`, connected to one of the system's input ports.
+    pub struct MidirInput {
+        rx: Receiver<MidiEvent>,
+        _connection: MidiInputConnection<()>,
+    }
+
+    impl MidirInput {
+        const CLIENT_NAME: &'static str = "synth";
+
+        /// Names of the available input ports, in the order `connect` indexes them. A port whose
+        /// name can't be read is listed as `<port N>`, so the indices stay aligned.
+        pub fn port_names() -> Result<Vec<String>, MidirError> {
+            let input = MidiInput::new(Self::CLIENT_NAME)?;
+
+            Ok(input
+                .ports()
+                .iter()
+                .enumerate()
+                .map(|(i, port)| {
+                    input
+                        .port_name(port)
+                        .unwrap_or_else(|_| format!("<port {}>", i))
+                })
+                .collect())
+        }
+
+        pub fn connect(port: usize) -> Result<Self, MidirError> {
+            let input = MidiInput::new(Self::CLIENT_NAME)?;
+
+            let input_port = input
+                .ports()
+                .get(port)
+                .cloned()
+                .ok_or(MidirError::NoSuchPort(port))?;
+
+            let (tx, rx) = mpsc::channel();
+
+            let connection = input
+                .connect(
+                    &input_port,
+                    Self::CLIENT_NAME,
+                    move |_, bytes, _| {
+                        if let Some(event) = MidiEvent::parse(bytes) {
+                            let _ = tx.send(event);
+                        }
+                    },
+                    (),
+                )
+                .map_err(|e| MidirError::Connect(e.kind()))?;
+
+            Ok(Self {
+                rx,
+                _connection: connection,
+            })
+        }
+    }
+
+    impl MidiTransport for MidirInput {
+        fn try_recv(&mut self) -> Option<MidiEvent> {
+            self.rx.try_recv().ok()
+        }
+    }
+}
@@ -1,5 +1,7 @@
 mod add;
 mod mul;
+mod vca;
 
 pub use add::Add;
 pub use mul::Mul;
+pub use vca::{Response, Vca};
@@ -0,0 +1,68 @@
+use dasp_graph::{Buffer, Input, Node};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// Gain follows the control voltage directly.
+    Linear,
+    /// Control voltage between 0 and 1 sweeps the gain over 60dB, reaching silence at 0.
+    Exponential,
+}
+
+impl Response {
+    const EXPONENTIAL_RANGE_DB: f32 = 60.0;
+
+    pub fn gain(self, cv: f32) -> f32 {
+        match self {
+            Response::Linear => cv,
+            Response::Exponential => {
+                if cv <= 0.0 {
+                    0.0
+                } else {
+                    let db = (cv.min(1.0) - 1.0) * Self::EXPONENTIAL_RANGE_DB;
+                    10_f32.powf(db / 20.0)
+                }
+            }
+        }
+    }
+}
+
+/// Scales input 0 by the gain that input 1 sets through the response curve.
+///
+/// Without a control input the audio passes through at unity gain.
+pub struct Vca {
+    response: Response,
+}
+
+impl Vca {
+    const AUDIO_INDEX: usize = 0;
+    const CV_INDEX: usize = 1;
+
+    pub fn new(response: Response) -> Self {
+        Self { response }
+    }
+}
+
+impl Node for Vca {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let audio_buf = inputs
+            .get(Self::AUDIO_INDEX)
+            .map(|input| &input.buffers()[0])
+            .unwrap_or(&Buffer::SILENT);
+        let cv_buf = inputs.get(Self::CV_INDEX).map(|input| &input.buffers()[0]);
+
+        for i in 0..Buffer::LEN {
+            let gain = cv_buf.map_or(1.0, |cv_buf| self.response.gain(cv_buf[i]));
+            let sample = audio_buf[i] * gain;
+
+            for buffer in output.iter_mut() {
+                buffer[i] = sample;
+            }
+        }
+    }
+}
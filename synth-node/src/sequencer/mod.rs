@@ -0,0 +1,3 @@
+mod step_sequence;
+
+pub use step_sequence::{Direction, SequenceCommand, Step, StepSequence};
@@ -0,0 +1,305 @@
+use crate::{source::Clock, util::Rng};
+
+use dasp_graph::{Buffer, Input, Node};
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    pub value: f32,
+    pub gate: bool,
+    /// Fraction of the clock interval the gate stays high for. Lengths of `1.0` or more hold the
+    /// gate until the next step.
+    pub length: f32,
+}
+
+impl Step {
+    pub fn new(value: f32) -> Self {
+        Self {
+            value,
+            gate: true,
+            length: 0.5,
+        }
+    }
+
+    pub fn rest(value: f32) -> Self {
+        Self {
+            gate: false,
+            ..Self::new(value)
+        }
+    }
+
+    pub fn with_length(mut self, length: f32) -> Self {
+        self.length = length;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Reverse,
+    /// Bounces between the first and last active steps without repeating them.
+    PingPong,
+    Random,
+}
+
+pub enum SequenceCommand {
+    SetValue(usize, f32),
+    SetGate(usize, bool),
+    SetLength(usize, f32),
+    SetStep(usize, Step),
+    SetActiveSteps(usize),
+    SetDirection(Direction),
+    Reset,
+}
+
+/// Steps through up to `N` values on each rising edge of its clock, with a gate per step.
+///
+/// Output buffer 0 holds the value of the current step and buffer 1 its gate, which rises to
+/// `Clock::HIGH` one sample after the clock and falls after the step's length, measured against
+/// the last clock interval. Until two clock edges have been seen the interval is unknown, so gates
+/// are held until the next step.
+///
+/// Input 0 is the clock. Input 1 is an optional reset, after which the next clock edge plays the
+/// first step again. Both rise once they reach the threshold, which is `Clock::HIGH` unless set
+/// with `with_threshold`.
+pub struct StepSequence<const N: usize> {
+    steps: [Step; N],
+    active_steps: usize,
+    direction: Direction,
+    current: usize,
+    ascending: bool,
+    started: bool,
+    interval: Option<u64>,
+    since_clock: u64,
+    gate_remaining: u64,
+    threshold: f32,
+    clock_high: bool,
+    reset_high: bool,
+    rng: Rng,
+    rx: Option<Receiver<SequenceCommand>>,
+}
+
+impl<const N: usize> StepSequence<N> {
+    pub const VALUE_BUFFER: usize = 0;
+    pub const GATE_BUFFER: usize = 1;
+
+    const CLOCK_INDEX: usize = 0;
+    const RESET_INDEX: usize = 1;
+
+    pub fn new(steps: [Step; N]) -> Self {
+        Self {
+            steps,
+            active_steps: N,
+            direction: Direction::Forward,
+            current: 0,
+            ascending: true,
+            started: false,
+            interval: None,
+            since_clock: 0,
+            gate_remaining: 0,
+            threshold: Clock::HIGH,
+            clock_high: false,
+            reset_high: false,
+            rng: Rng::from_entropy(),
+            rx: None,
+        }
+    }
+
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets the level the clock and reset rise at, such as `0.5` for a unipolar square LFO.
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Only plays the first `active_steps` steps, clamped to between 1 and `N`.
+    pub fn with_active_steps(mut self, active_steps: usize) -> Self {
+        self.set_active_steps(active_steps);
+        self
+    }
+
+    /// Makes the random direction repeat exactly for a given seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::from_seed(seed);
+        self
+    }
+
+    pub fn with_channel(mut self) -> (Self, Sender<SequenceCommand>) {
+        let (tx, rx) = mpsc::channel();
+        self.rx = Some(rx);
+        (self, tx)
+    }
+
+    fn set_active_steps(&mut self, active_steps: usize) {
+        self.active_steps = active_steps.clamp(1, N.max(1));
+    }
+
+    fn process_commands(&mut self) {
+        while let Some(command) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            match command {
+                SequenceCommand::SetValue(index, value) => {
+                    if let Some(step) = self.steps.get_mut(index) {
+                        step.value = value;
+                    }
+                }
+                SequenceCommand::SetGate(index, gate) => {
+                    if let Some(step) = self.steps.get_mut(index) {
+                        step.gate = gate;
+                    }
+                }
+                SequenceCommand::SetLength(index, length) => {
+                    if let Some(step) = self.steps.get_mut(index) {
+                        step.length = length;
+                    }
+                }
+                SequenceCommand::SetStep(index, new_step) => {
+                    if let Some(step) = self.steps.get_mut(index) {
+                        *step = new_step;
+                    }
+                }
+                SequenceCommand::SetActiveSteps(active_steps) => {
+                    self.set_active_steps(active_steps);
+                }
+                SequenceCommand::SetDirection(direction) => {
+                    self.direction = direction;
+                }
+                SequenceCommand::Reset => {
+                    self.reset();
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.started = false;
+        self.ascending = true;
+        self.gate_remaining = 0;
+    }
+
+    fn first_step(&self) -> usize {
+        match self.direction {
+            Direction::Reverse => self.active_steps - 1,
+            _ => 0,
+        }
+    }
+
+    fn next_step(&mut self) -> usize {
+        let len = self.active_steps;
+        let current = self.current.min(len - 1);
+
+        match self.direction {
+            Direction::Forward => (current + 1) % len,
+            Direction::Reverse => (current + len - 1) % len,
+            Direction::PingPong => {
+                if len == 1 {
+                    return 0;
+                }
+
+                if self.ascending && current + 1 >= len {
+                    self.ascending = false;
+                } else if !self.ascending && current == 0 {
+                    self.ascending = true;
+                }
+
+                if self.ascending {
+                    current + 1
+                } else {
+                    current - 1
+                }
+            }
+            Direction::Random => (self.rng.next_u64() % len as u64) as usize,
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.started {
+            self.interval = Some(self.since_clock);
+            self.current = self.next_step();
+        } else {
+            self.started = true;
+            self.current = self.first_step();
+        }
+
+        self.since_clock = 0;
+
+        let step = self.steps[self.current];
+
+        self.gate_remaining = if !step.gate {
+            0
+        } else {
+            match self.interval {
+                Some(interval) if step.length < 1.0 => {
+                    (interval as f32 * step.length.max(0.0)).round() as u64
+                }
+                _ => u64::MAX,
+            }
+        };
+    }
+}
+
+impl<const N: usize> Node for StepSequence<N> {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        self.process_commands();
+
+        if N == 0 {
+            for buffer in output.iter_mut() {
+                *buffer = Buffer::SILENT;
+            }
+
+            return;
+        }
+
+        let input_sample =
+            |index: usize, i: usize| inputs.get(index).map(|input: &Input| input.buffers()[0][i]);
+
+        for i in 0..Buffer::LEN {
+            if let Some(reset) = input_sample(Self::RESET_INDEX, i) {
+                let reset_high = reset >= self.threshold;
+
+                if reset_high && !self.reset_high {
+                    self.reset();
+                }
+
+                self.reset_high = reset_high;
+            }
+
+            let clock_high =
+                input_sample(Self::CLOCK_INDEX, i).unwrap_or(Clock::LOW) >= self.threshold;
+
+            // The gate drops for the clock sample itself so that back-to-back gates retrigger.
+            let gate = if clock_high && !self.clock_high {
+                self.advance();
+                Clock::LOW
+            } else if self.gate_remaining > 0 {
+                self.gate_remaining -= 1;
+                Clock::HIGH
+            } else {
+                Clock::LOW
+            };
+
+            self.clock_high = clock_high;
+            self.since_clock += 1;
+
+            let value = self.steps[self.current.min(N - 1)].value;
+
+            for (index, buffer) in output.iter_mut().enumerate() {
+                buffer[i] = match index {
+                    Self::GATE_BUFFER => gate,
+                    _ => value,
+                };
+            }
+        }
+    }
+}
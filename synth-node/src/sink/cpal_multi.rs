@@ -0,0 +1,53 @@
+use super::{CpalOutput, CpalSinkError, CpalSinkOptions, SinkMonitor};
+
+use dasp_graph::{Buffer, Input, Node};
+
+/// Plays its inputs on a CPAL output stream, one buffer per device channel.
+///
+/// The buffers of all inputs are laid out in order, so buffer `N` feeds channel `N`: two mono
+/// inputs, or a single stereo input, fill a stereo device. Channels without a matching buffer
+/// repeat the first one, which means a single mono input is played on every channel. When there
+/// are no inputs at all the device is fed silence.
+pub struct CpalSink {
+    output: CpalOutput,
+}
+
+impl CpalSink {
+    pub fn new(
+        device: &cpal::Device,
+        config: &cpal::SupportedStreamConfig,
+    ) -> Result<(Self, SinkMonitor), CpalSinkError> {
+        Self::with_options(device, config, &CpalSinkOptions::default())
+    }
+
+    pub fn with_options(
+        device: &cpal::Device,
+        config: &cpal::SupportedStreamConfig,
+        options: &CpalSinkOptions,
+    ) -> Result<(Self, SinkMonitor), CpalSinkError> {
+        let stream_config = config.config();
+
+        let (output, monitor) = match config.sample_format() {
+            cpal::SampleFormat::F32 => CpalOutput::new::<f32>(device, &stream_config, options)?,
+            cpal::SampleFormat::I16 => CpalOutput::new::<i16>(device, &stream_config, options)?,
+            cpal::SampleFormat::U16 => CpalOutput::new::<u16>(device, &stream_config, options)?,
+        };
+
+        Ok((Self { output }, monitor))
+    }
+
+    fn channel_buffer(inputs: &[Input], channel: usize) -> Option<&Buffer> {
+        inputs.iter().flat_map(|input| input.buffers()).nth(channel)
+    }
+}
+
+impl Node for CpalSink {
+    fn process(&mut self, inputs: &[Input], _output: &mut [Buffer]) {
+        self.output.write_block(|i, channel| {
+            Self::channel_buffer(inputs, channel)
+                .or_else(|| Self::channel_buffer(inputs, 0))
+                .map(|buffer| buffer[i])
+                .unwrap_or_default()
+        });
+    }
+}
@@ -0,0 +1,238 @@
+use cpal::traits::{DeviceTrait, StreamTrait};
+use dasp_graph::Buffer;
+use rtrb::{Consumer, Producer, RingBuffer};
+
+use std::{
+    error, fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex,
+    },
+    thread::{self, Thread},
+    time::Duration,
+};
+
+/// What a CPAL sink does with a block when its ring buffer has no room for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnFull {
+    /// Park the graph thread until the audio callback has made room.
+    Park,
+    /// Drop the block and count it as an overrun.
+    Drop,
+}
+
+#[derive(Clone, Debug)]
+pub struct CpalSinkOptions {
+    /// Capacity of the ring buffer between the graph and the audio callback, in frames.
+    pub ring_buffer_frames: usize,
+    /// Buffer size requested from the device, which sets the stream latency.
+    pub buffer_size: cpal::BufferSize,
+    pub on_full: OnFull,
+}
+
+impl Default for CpalSinkOptions {
+    fn default() -> Self {
+        Self {
+            ring_buffer_frames: 4096,
+            buffer_size: cpal::BufferSize::Default,
+            on_full: OnFull::Park,
+        }
+    }
+}
+
+/// Why a CPAL sink couldn't start its output stream.
+#[derive(Debug)]
+pub enum CpalSinkError {
+    Build(cpal::BuildStreamError),
+    Play(cpal::PlayStreamError),
+}
+
+impl fmt::Display for CpalSinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpalSinkError::Build(e) => write!(f, "failed to build output stream: {}", e),
+            CpalSinkError::Play(e) => write!(f, "failed to start output stream: {}", e),
+        }
+    }
+}
+
+impl error::Error for CpalSinkError {}
+
+impl From<cpal::BuildStreamError> for CpalSinkError {
+    fn from(e: cpal::BuildStreamError) -> Self {
+        CpalSinkError::Build(e)
+    }
+}
+
+impl From<cpal::PlayStreamError> for CpalSinkError {
+    fn from(e: cpal::PlayStreamError) -> Self {
+        CpalSinkError::Play(e)
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    underruns: AtomicU64,
+    overruns: AtomicU64,
+}
+
+/// Reports on a running CPAL sink from outside the graph.
+pub struct SinkMonitor {
+    stats: Arc<Stats>,
+    errors: Receiver<cpal::StreamError>,
+}
+
+impl SinkMonitor {
+    /// Number of device frames that were played as silence because the graph fell behind.
+    pub fn underruns(&self) -> u64 {
+        self.stats.underruns.load(Ordering::Relaxed)
+    }
+
+    /// Number of blocks dropped because the ring buffer was full. Only counted with
+    /// `OnFull::Drop`.
+    pub fn overruns(&self) -> u64 {
+        self.stats.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Returns the next error reported by the stream, if any.
+    pub fn try_recv_error(&self) -> Option<cpal::StreamError> {
+        match self.errors.try_recv() {
+            Ok(error) => Some(error),
+            Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => None,
+        }
+    }
+}
+
+/// The device side shared by the CPAL sinks: a started output stream fed whole interleaved
+/// frames through a ring buffer.
+pub(crate) struct CpalOutput {
+    producer: Producer<f32>,
+    channels: usize,
+    on_full: OnFull,
+    wait: Duration,
+    stats: Arc<Stats>,
+    graph_thread: Arc<Mutex<Option<Thread>>>,
+    _stream: cpal::Stream,
+}
+
+impl CpalOutput {
+    pub(crate) fn new<T: cpal::Sample>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        options: &CpalSinkOptions,
+    ) -> Result<(Self, SinkMonitor), CpalSinkError> {
+        let channels = config.channels as usize;
+        let frames = options.ring_buffer_frames.max(Buffer::LEN);
+
+        let (producer, consumer) = RingBuffer::<f32>::new(frames * channels);
+        let (error_tx, error_rx) = mpsc::channel();
+
+        let stats = Arc::new(Stats::default());
+        let graph_thread = Arc::new(Mutex::new(None));
+
+        let config = cpal::StreamConfig {
+            buffer_size: options.buffer_size.clone(),
+            ..config.clone()
+        };
+
+        let stream = Self::build_stream::<T>(
+            device,
+            &config,
+            consumer,
+            stats.clone(),
+            graph_thread.clone(),
+            error_tx,
+        )?;
+        stream.play()?;
+
+        let wait = Duration::from_secs_f32(Buffer::LEN as f32 / config.sample_rate.0 as f32);
+
+        let output = Self {
+            producer,
+            channels,
+            on_full: options.on_full,
+            wait,
+            stats: stats.clone(),
+            graph_thread,
+            _stream: stream,
+        };
+
+        let monitor = SinkMonitor {
+            stats,
+            errors: error_rx,
+        };
+
+        Ok((output, monitor))
+    }
+
+    fn build_stream<T: cpal::Sample>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mut consumer: Consumer<f32>,
+        stats: Arc<Stats>,
+        graph_thread: Arc<Mutex<Option<Thread>>>,
+        error_tx: Sender<cpal::StreamError>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError> {
+        let channels = config.channels as usize;
+
+        device.build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                data.chunks_mut(channels).for_each(|frame| {
+                    // Only take whole frames so the channels never drift out of alignment.
+                    if consumer.slots() >= frame.len() {
+                        frame.iter_mut().for_each(|sample| {
+                            *sample = T::from(&consumer.pop().unwrap_or_default());
+                        });
+                    } else {
+                        stats.underruns.fetch_add(1, Ordering::Relaxed);
+                        frame.iter_mut().for_each(|sample| {
+                            *sample = T::from(&0.0);
+                        });
+                    }
+                });
+
+                if let Ok(graph_thread) = graph_thread.try_lock() {
+                    if let Some(graph_thread) = graph_thread.as_ref() {
+                        graph_thread.unpark();
+                    }
+                }
+            },
+            move |e| {
+                let _ = error_tx.send(e);
+            },
+        )
+    }
+
+    /// Queues a block of `Buffer::LEN` interleaved frames, where `sample(i, channel)` gives the
+    /// value of `channel` in frame `i`.
+    pub(crate) fn write_block<F>(&mut self, mut sample: F)
+    where
+        F: FnMut(usize, usize) -> f32,
+    {
+        let needed = Buffer::LEN * self.channels;
+
+        while self.producer.slots() < needed {
+            match self.on_full {
+                OnFull::Park => {
+                    if let Ok(mut graph_thread) = self.graph_thread.lock() {
+                        *graph_thread = Some(thread::current());
+                    }
+
+                    thread::park_timeout(self.wait);
+                }
+                OnFull::Drop => {
+                    self.stats.overruns.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+
+        for i in 0..Buffer::LEN {
+            for channel in 0..self.channels {
+                self.producer.push(sample(i, channel)).unwrap();
+            }
+        }
+    }
+}
@@ -1,3 +1,11 @@
 mod cpal_mono;
+mod cpal_multi;
+mod cpal_output;
+mod wav;
+
+use cpal_output::CpalOutput;
 
 pub use cpal_mono::CpalMonoSink;
+pub use cpal_multi::CpalSink;
+pub use cpal_output::{CpalSinkError, CpalSinkOptions, OnFull, SinkMonitor};
+pub use wav::{WavFormat, WavSink};
@@ -0,0 +1,228 @@
+use dasp_graph::{Buffer, Input, Node};
+use hound::{SampleFormat, WavSpec, WavWriter};
+
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, Write},
+    path::Path,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WavFormat {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl WavFormat {
+    fn spec(self, sample_rate: u32, channels: u16) -> WavSpec {
+        let (bits_per_sample, sample_format) = match self {
+            WavFormat::Int16 => (16, SampleFormat::Int),
+            WavFormat::Int24 => (24, SampleFormat::Int),
+            WavFormat::Float32 => (32, SampleFormat::Float),
+        };
+
+        WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+}
+
+/// Writes its inputs to a WAV file, one input per channel.
+///
+/// Input `N` feeds channel `N`. Channels without a matching input repeat the first input, so a
+/// single mono input is written to every channel. Rendering stops once the optional duration has
+/// been reached, at which point the header is finalised; otherwise it is finalised on drop.
+///
+/// Writing stops at the first I/O error, which is kept until `take_error` or `finalize` returns
+/// it.
+pub struct WavSink<W: Write + Seek> {
+    writer: Option<WavWriter<W>>,
+    format: WavFormat,
+    channels: usize,
+    remaining: Option<usize>,
+    error: Option<hound::Error>,
+}
+
+impl WavSink<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        sample_rate: u32,
+        channels: u16,
+        format: WavFormat,
+    ) -> hound::Result<Self> {
+        let writer = WavWriter::create(path, format.spec(sample_rate, channels))?;
+        Ok(Self::from_writer(writer, format))
+    }
+}
+
+impl<W: Write + Seek> WavSink<W> {
+    pub fn new(
+        writer: W,
+        sample_rate: u32,
+        channels: u16,
+        format: WavFormat,
+    ) -> hound::Result<Self> {
+        let writer = WavWriter::new(writer, format.spec(sample_rate, channels))?;
+        Ok(Self::from_writer(writer, format))
+    }
+
+    fn from_writer(writer: WavWriter<W>, format: WavFormat) -> Self {
+        Self {
+            channels: writer.spec().channels as usize,
+            writer: Some(writer),
+            format,
+            remaining: None,
+            error: None,
+        }
+    }
+
+    /// Limits the render to `frames` sample frames, after which the file is finalised.
+    pub fn with_duration(mut self, frames: usize) -> Self {
+        self.remaining = Some(frames);
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.writer.is_none()
+    }
+
+    /// Finalises the header, or returns the error that stopped the render.
+    pub fn finalize(&mut self) -> hound::Result<()> {
+        if let Some(error) = self.take_error() {
+            return Err(error);
+        }
+
+        match self.writer.take() {
+            Some(writer) => writer.finalize(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the error that stopped the render, if any.
+    pub fn take_error(&mut self) -> Option<hound::Error> {
+        self.error.take()
+    }
+
+    /// Stops writing and keeps `error` to be reported later.
+    fn fail(&mut self, error: hound::Error) {
+        self.writer = None;
+        self.error = Some(error);
+    }
+
+    fn write_sample(
+        writer: &mut WavWriter<W>,
+        format: WavFormat,
+        sample: f32,
+    ) -> hound::Result<()> {
+        match format {
+            WavFormat::Int16 => {
+                let sample = sample.clamp(-1.0, 1.0) * i16::MAX as f32;
+                writer.write_sample(sample as i16)
+            }
+            WavFormat::Int24 => {
+                let sample = sample.clamp(-1.0, 1.0) * 8_388_607.0;
+                writer.write_sample(sample as i32)
+            }
+            WavFormat::Float32 => writer.write_sample(sample),
+        }
+    }
+}
+
+impl<W: Write + Seek> Node for WavSink<W> {
+    fn process(&mut self, inputs: &[Input], _output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return,
+        };
+
+        let frames = match self.remaining {
+            Some(remaining) => remaining.min(Buffer::LEN),
+            None => Buffer::LEN,
+        };
+
+        for i in 0..frames {
+            for channel in 0..self.channels {
+                let sample = inputs
+                    .get(channel)
+                    .or_else(|| inputs.first())
+                    .map(|input| input.buffers()[0][i])
+                    .unwrap_or_default();
+
+                if let Err(error) = Self::write_sample(writer, self.format, sample) {
+                    self.fail(error);
+                    return;
+                }
+            }
+        }
+
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= frames;
+
+            if *remaining == 0 {
+                if let Err(error) = self.finalize() {
+                    self.error = Some(error);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{self, Cursor, SeekFrom};
+
+    /// Accepts `capacity` bytes, then fails every write.
+    struct FullDisk {
+        inner: Cursor<Vec<u8>>,
+        capacity: usize,
+    }
+
+    impl Write for FullDisk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.inner.get_ref().len() + buf.len() > self.capacity {
+                return Err(io::Error::other("disk full"));
+            }
+
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for FullDisk {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn write_error_is_kept_instead_of_panicking() {
+        let disk = FullDisk {
+            inner: Cursor::new(Vec::new()),
+            capacity: 100,
+        };
+        let mut sink = WavSink::new(disk, 44_100, 1, WavFormat::Int16).unwrap();
+
+        for _ in 0..4 {
+            sink.process(&[], &mut []);
+        }
+
+        assert!(sink.is_finished());
+        assert!(sink.take_error().is_some());
+        assert!(sink.finalize().is_ok());
+    }
+}
@@ -0,0 +1,80 @@
+//! Polynomial corrections for band-limiting naive waveforms.
+//!
+//! Both take the phase `t` relative to a discontinuity, in `[0, 1)`, and the phase increment per
+//! sample `dt`, and return the residual to add for a unit-sized discontinuity.
+
+/// Residual of a unit upward step in value.
+pub(crate) fn poly_blep(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let x = t / dt;
+        -(1.0 - x) * (1.0 - x) / 2.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt;
+        (1.0 + x) * (1.0 + x) / 2.0
+    } else {
+        0.0
+    }
+}
+
+/// Residual of a unit increase in slope, measured per sample.
+pub(crate) fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let x = 1.0 - t / dt;
+        x * x * x / 6.0
+    } else if t > 1.0 - dt {
+        let x = 1.0 + (t - 1.0) / dt;
+        x * x * x / 6.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::source::{Saw, Square, Triangle};
+
+    use dasp_graph::{Buffer, Node};
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// Peak of a second of output, failing on anything that isn't finite.
+    fn peak<N: Node>(mut node: N) -> f32 {
+        let mut output = [Buffer::SILENT];
+        let mut peak = 0.0f32;
+
+        for _ in 0..(SAMPLE_RATE as usize / Buffer::LEN) {
+            node.process(&[], &mut output);
+
+            for sample in output[0].iter() {
+                assert!(sample.is_finite());
+                peak = peak.max(sample.abs());
+            }
+        }
+
+        peak
+    }
+
+    #[test]
+    fn band_limited_output_is_finite_and_bounded() {
+        for freq in [20.0, 440.0, 5_000.0, 15_000.0, 21_000.0, 30_000.0] {
+            assert!(peak(Saw::new(freq, SAMPLE_RATE).band_limited()) <= 1.01);
+            assert!(peak(Square::new(freq, SAMPLE_RATE).band_limited()) <= 1.01);
+            assert!(peak(Triangle::new(freq, SAMPLE_RATE).band_limited()) <= 1.01);
+
+            for pulse_width in [0.01, 0.99] {
+                let square = Square::new(freq, SAMPLE_RATE)
+                    .with_pulse_width(pulse_width)
+                    .band_limited();
+
+                assert!(peak(square) <= 1.01);
+            }
+        }
+    }
+
+    #[test]
+    fn band_limited_output_keeps_its_level_at_low_frequencies() {
+        assert!(peak(Saw::new(110.0, SAMPLE_RATE).band_limited()) > 0.95);
+        assert!(peak(Square::new(110.0, SAMPLE_RATE).band_limited()) > 0.95);
+        assert!(peak(Triangle::new(110.0, SAMPLE_RATE).band_limited()) > 0.95);
+    }
+}
@@ -1,46 +1,273 @@
 use dasp_graph::{Buffer, Input, Node};
 
+use std::sync::mpsc::{self, Receiver, Sender};
+
+pub enum ClockCommand {
+    DeltaBpm(f32),
+    SetBpm(f32),
+    Start,
+    Stop,
+    Reset,
+}
+
+/// Emits one-sample `Clock::HIGH` pulses at a tempo in beats per minute.
+///
+/// Time is kept as a fractional beat position, so pulses land on the nearest sample to the ideal
+/// grid and never drift. The first pulse arrives one interval after starting or resetting.
+///
+/// Output buffer `N` pulses at `N`th ratio given to `with_ratios` times the tempo, so a ratio of
+/// `0.5` divides the clock by two and `4.0` multiplies it by four. Buffers without a ratio pulse
+/// at the tempo. Swing delays every second pulse of each output by a fraction of its interval.
+///
+/// Input 0 is an optional run gate, which starts the clock when it rises to `Clock::HIGH` and stops
+/// it when it falls to `Clock::LOW`. The gate only acts when it changes, so `Start` and `Stop`
+/// commands still work while it is patched, and whichever changed last wins. Input 1 is an
+/// optional reset, which restarts the clock on each rising edge.
+///
+/// The tempo is limited so the fastest output pulses at most every other sample. Pulses that swing
+/// would put on the same sample are merged into one.
 pub struct Clock {
-    interval: u32,
-    completed: u32,
+    bpm: f32,
+    sample_rate: f32,
+    swing: f32,
+    ratios: Vec<f32>,
+    next_pulses: Vec<u64>,
+    beats: f64,
+    running: bool,
+    run_high: bool,
+    reset_high: bool,
+    rx: Option<Receiver<ClockCommand>>,
 }
 
 impl Clock {
     pub const HIGH: f32 = 5.0;
     pub const LOW: f32 = 0.0;
 
-    pub fn new(bpm: f32, sample_rate: u32) -> Self {
-        let interval_s = 60.0 / bpm;
-        let interval_samples = (interval_s * sample_rate as f32).ceil() as u32;
+    const RUN_INDEX: usize = 0;
+    const RESET_INDEX: usize = 1;
 
+    pub fn new(bpm: f32, sample_rate: u32) -> Self {
         Self {
-            interval: interval_samples,
-            completed: 0,
+            bpm,
+            sample_rate: sample_rate as f32,
+            swing: 0.0,
+            ratios: vec![1.0],
+            next_pulses: vec![1],
+            beats: 0.0,
+            running: true,
+            run_high: true,
+            reset_high: false,
+            rx: None,
+        }
+    }
+
+    pub fn with_ratios(mut self, ratios: &[f32]) -> Self {
+        self.ratios = ratios.to_vec();
+        self.next_pulses = vec![1; ratios.len()];
+        self
+    }
+
+    /// Delays every second pulse by `swing` of an interval, where `1.0 / 3.0` gives a triplet
+    /// shuffle.
+    pub fn with_swing(mut self, swing: f32) -> Self {
+        self.swing = swing.clamp(0.0, 0.99);
+        self
+    }
+
+    pub fn with_channel(mut self) -> (Self, Sender<ClockCommand>) {
+        let (tx, rx) = mpsc::channel();
+        self.rx = Some(rx);
+        (self, tx)
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    fn process_commands(&mut self) {
+        while let Some(command) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            match command {
+                ClockCommand::DeltaBpm(delta) => {
+                    self.bpm += delta;
+                }
+                ClockCommand::SetBpm(bpm) => {
+                    self.bpm = bpm;
+                }
+                ClockCommand::Start => {
+                    self.running = true;
+                }
+                ClockCommand::Stop => {
+                    self.running = false;
+                }
+                ClockCommand::Reset => {
+                    self.reset();
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.beats = 0.0;
+
+        for next_pulse in self.next_pulses.iter_mut() {
+            *next_pulse = 1;
+        }
+    }
+
+    fn ratio(&self, output: usize) -> f64 {
+        self.ratios.get(output).copied().unwrap_or(1.0) as f64
+    }
+
+    /// Beats per sample at the current tempo, limited so none of `outputs` pulses more than every
+    /// other sample.
+    fn beats_per_sample(&self, outputs: usize) -> f64 {
+        let max_ratio = (0..outputs)
+            .map(|output| self.ratio(output).abs())
+            .fold(0.0, f64::max);
+        let beats_per_sample = self.bpm.max(0.0) as f64 / 60.0 / self.sample_rate as f64;
+
+        if max_ratio > 0.0 {
+            beats_per_sample.min(0.5 / max_ratio)
+        } else {
+            beats_per_sample
+        }
+    }
+
+    /// Position of pulse `n` of an output, in that output's intervals.
+    fn pulse_position(&self, n: u64) -> f64 {
+        if n % 2 == 1 {
+            n as f64 + self.swing as f64
+        } else {
+            n as f64
         }
     }
 }
 
 impl Node for Clock {
     fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
-        if !inputs.is_empty() {
-            panic!();
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        self.process_commands();
+
+        if self.next_pulses.len() < output.len() {
+            self.next_pulses.resize(output.len(), 1);
         }
 
+        let input_sample =
+            |index: usize, i: usize| inputs.get(index).map(|input: &Input| input.buffers()[0][i]);
+
+        let beats_per_sample = self.beats_per_sample(output.len());
+
         for i in 0..Buffer::LEN {
-            let sample = if self.completed == self.interval {
-                Self::HIGH
-            } else {
-                Self::LOW
-            };
-
-            self.completed += 1;
-            if self.completed > self.interval {
-                self.completed = 0;
+            if let Some(run) = input_sample(Self::RUN_INDEX, i) {
+                let run_high = if run >= Self::HIGH {
+                    true
+                } else if run <= Self::LOW {
+                    false
+                } else {
+                    self.run_high
+                };
+
+                if run_high != self.run_high {
+                    self.running = run_high;
+                }
+
+                self.run_high = run_high;
             }
 
-            for buffer in output.iter_mut() {
-                buffer[i] = sample;
+            if let Some(reset) = input_sample(Self::RESET_INDEX, i) {
+                let reset_high = reset >= Self::HIGH;
+
+                if reset_high && !self.reset_high {
+                    self.reset();
+                }
+
+                self.reset_high = reset_high;
+            }
+
+            if self.running {
+                self.beats += beats_per_sample;
+            }
+
+            for (index, buffer) in output.iter_mut().enumerate() {
+                let position = self.beats * self.ratio(index);
+                let mut next_pulse = self.next_pulses[index];
+
+                buffer[i] = if self.running && position >= self.pulse_position(next_pulse) {
+                    while position >= self.pulse_position(next_pulse) {
+                        next_pulse += 1;
+                    }
+
+                    self.next_pulses[index] = next_pulse;
+                    Self::HIGH
+                } else {
+                    Self::LOW
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::process_patched;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn pulses(output: &Buffer) -> usize {
+        output
+            .iter()
+            .filter(|&&sample| sample == Clock::HIGH)
+            .count()
+    }
+
+    #[test]
+    fn stop_command_holds_while_run_gate_stays_high() {
+        let (mut clock, tx) = Clock::new(6_000.0, SAMPLE_RATE).with_channel();
+
+        let mut run = Buffer::SILENT;
+        run.iter_mut().for_each(|sample| *sample = Clock::HIGH);
+
+        tx.send(ClockCommand::Stop).unwrap();
+
+        for _ in 0..100 {
+            let output = process_patched(&mut clock, &[run.clone()], 1);
+            assert_eq!(pulses(&output[0]), 0);
+        }
+
+        tx.send(ClockCommand::Start).unwrap();
+        let mut total = 0;
+
+        for _ in 0..100 {
+            let output = process_patched(&mut clock, &[run.clone()], 1);
+            total += pulses(&output[0]);
+        }
+
+        assert!(total > 0);
+    }
+
+    #[test]
+    fn fast_tempo_pulses_at_most_every_other_sample() {
+        let mut clock = Clock::new(1.0e9, SAMPLE_RATE)
+            .with_ratios(&[1.0, 4.0])
+            .with_swing(0.9);
+        let mut output = [Buffer::SILENT, Buffer::SILENT];
+
+        for _ in 0..10 {
+            clock.process(&[], &mut output);
+
+            for buffer in output.iter() {
+                for pair in buffer.windows(2) {
+                    assert!(pair[0] == Clock::LOW || pair[1] == Clock::LOW);
+                }
             }
         }
+
+        assert!(pulses(&output[1]) > 0);
     }
 }
@@ -0,0 +1,223 @@
+use super::{Clock, SawWave, SineWave, SquareWave, TriangleWave, Waveform};
+
+use dasp_graph::{Buffer, Input, Node};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Polarity {
+    /// Swings between `-depth` and `depth`.
+    Bipolar,
+    /// Swings between 0 and `depth`.
+    Unipolar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LfoRate {
+    /// A free-running rate in cycles per second.
+    Hz(f32),
+    /// One cycle every given number of clock pulses, following the clock input. Counts below
+    /// `Lfo::MIN_BEATS`, including zero and negative ones, are raised to it.
+    Beats(f32),
+}
+
+/// Low-frequency oscillator for modulating v/oct, cutoff and other CV inputs.
+///
+/// Input 0 is a clock, which is only read with `LfoRate::Beats`. Until two clock pulses have been
+/// seen the phase stays at its offset. After that the phase moves on at the rate set by the last
+/// interval between pulses, so a change of tempo bends the rate without making the phase jump.
+pub struct Lfo {
+    rate: LfoRate,
+    shape: LfoShape,
+    polarity: Polarity,
+    depth: f32,
+    phase_offset: f32,
+    sample_rate: f32,
+    phase: f32,
+    clock_high: bool,
+    beat_seen: bool,
+    since_beat: u32,
+    beat_interval: Option<u32>,
+}
+
+impl Lfo {
+    /// Fewest clock pulses a cycle can take with `LfoRate::Beats`.
+    pub const MIN_BEATS: f32 = 1.0 / 64.0;
+
+    const CLOCK_INDEX: usize = 0;
+
+    pub fn new(rate: LfoRate, shape: LfoShape, sample_rate: u32) -> Self {
+        let rate = match rate {
+            LfoRate::Beats(beats) => LfoRate::Beats(beats.max(Self::MIN_BEATS)),
+            rate => rate,
+        };
+
+        Self {
+            rate,
+            shape,
+            polarity: Polarity::Bipolar,
+            depth: 1.0,
+            phase_offset: 0.0,
+            sample_rate: sample_rate as f32,
+            phase: 0.0,
+            clock_high: false,
+            beat_seen: false,
+            since_beat: 0,
+            beat_interval: None,
+        }
+    }
+
+    pub fn with_polarity(mut self, polarity: Polarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Offsets the phase by a fraction of a cycle.
+    pub fn with_phase_offset(mut self, phase_offset: f32) -> Self {
+        self.phase_offset = phase_offset;
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    fn advance(&mut self, clock: f32) {
+        match self.rate {
+            LfoRate::Hz(freq) => {
+                self.phase = (self.phase + freq / self.sample_rate).rem_euclid(1.0);
+            }
+            LfoRate::Beats(beats) => {
+                let clock_high = clock >= Clock::HIGH;
+
+                if clock_high && !self.clock_high {
+                    if self.beat_seen {
+                        self.beat_interval = Some(self.since_beat.max(1));
+                    }
+
+                    self.beat_seen = true;
+                    self.since_beat = 0;
+                }
+
+                self.clock_high = clock_high;
+
+                if let Some(interval) = self.beat_interval {
+                    let step = 1.0 / (interval as f32 * beats);
+                    self.phase = (self.phase + step).rem_euclid(1.0);
+                }
+
+                self.since_beat = self.since_beat.saturating_add(1);
+            }
+        }
+    }
+
+    pub fn get_sample(&mut self, clock: f32) -> f32 {
+        self.advance(clock);
+
+        let phase = (self.phase + self.phase_offset).rem_euclid(1.0);
+
+        let sample = match self.shape {
+            LfoShape::Sine => SineWave.sample(phase, 0.0),
+            LfoShape::Triangle => TriangleWave::default().sample(phase, 0.0),
+            LfoShape::Saw => SawWave::default().sample(phase, 0.0),
+            LfoShape::Square => SquareWave::default().sample(phase, 0.0),
+        };
+
+        let sample = match self.polarity {
+            Polarity::Bipolar => sample,
+            Polarity::Unipolar => (sample + 1.0) / 2.0,
+        };
+
+        sample * self.depth
+    }
+}
+
+impl Node for Lfo {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let clock_buf = inputs
+            .get(Self::CLOCK_INDEX)
+            .map(|input| &input.buffers()[0])
+            .unwrap_or(&Buffer::SILENT);
+
+        for i in 0..Buffer::LEN {
+            let sample = self.get_sample(clock_buf[i]);
+
+            for buffer in output.iter_mut() {
+                buffer[i] = sample;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    /// Runs `lfo` for `pulses` clock pulses `interval` samples apart, returning every sample.
+    fn clocked(lfo: &mut Lfo, interval: usize, pulses: usize) -> Vec<f32> {
+        (0..interval * pulses)
+            .map(|i| {
+                let clock = if i % interval == 0 {
+                    Clock::HIGH
+                } else {
+                    Clock::LOW
+                };
+                lfo.get_sample(clock)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn non_positive_beats_stay_finite() {
+        for beats in [0.0, -1.0, f32::NAN] {
+            let mut lfo = Lfo::new(LfoRate::Beats(beats), LfoShape::Sine, SAMPLE_RATE);
+
+            assert_eq!(lfo.rate, LfoRate::Beats(Lfo::MIN_BEATS));
+            assert!(clocked(&mut lfo, 100, 10)
+                .iter()
+                .all(|sample| sample.is_finite()));
+        }
+    }
+
+    #[test]
+    fn slowing_clock_never_moves_the_phase_back() {
+        let mut lfo = Lfo::new(LfoRate::Beats(4.0), LfoShape::Saw, SAMPLE_RATE);
+        let mut phases = Vec::new();
+
+        for interval in [100, 1_000] {
+            for i in 0..interval * 3 {
+                let clock = if i % interval == 0 {
+                    Clock::HIGH
+                } else {
+                    Clock::LOW
+                };
+                lfo.get_sample(clock);
+                phases.push(lfo.phase);
+            }
+        }
+
+        for pair in phases.windows(2) {
+            // The phase may only move forwards, wrapping around at the end of a cycle.
+            assert!(pair[1] >= pair[0] || pair[0] - pair[1] > 0.5);
+        }
+    }
+}
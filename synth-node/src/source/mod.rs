@@ -1,13 +1,24 @@
+mod blep;
 mod clock;
 mod level;
+mod lfo;
+mod noise;
+mod oscillator;
+mod param;
+mod sample_and_hold;
 mod saw;
 mod sine;
 mod square;
 mod triangle;
 
-pub use clock::Clock;
+pub use clock::{Clock, ClockCommand};
 pub use level::{Level, LevelCommand};
-pub use saw::Saw;
-pub use sine::Sine;
-pub use square::Square;
-pub use triangle::Triangle;
+pub use lfo::{Lfo, LfoRate, LfoShape, Polarity};
+pub use noise::{BrownNoise, PinkNoise, WhiteNoise};
+pub use oscillator::{FmMode, Oscillator, Waveform};
+pub use param::{Param, ParamSpec, ParamTarget};
+pub use sample_and_hold::SampleAndHold;
+pub use saw::{Saw, SawWave};
+pub use sine::{Sine, SineWave};
+pub use square::{Square, SquareWave};
+pub use triangle::{Triangle, TriangleWave};
@@ -0,0 +1,193 @@
+use crate::util::Rng;
+
+use dasp_graph::{Buffer, Input, Node};
+
+/// Uniform white noise between -1 and 1.
+pub struct WhiteNoise {
+    rng: Rng,
+}
+
+impl WhiteNoise {
+    pub fn new() -> Self {
+        Self {
+            rng: Rng::from_entropy(),
+        }
+    }
+
+    /// Makes the noise repeat exactly for a given seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::from_seed(seed);
+        self
+    }
+
+    pub fn get_sample(&mut self) -> f32 {
+        self.rng.next_bipolar()
+    }
+}
+
+impl Default for WhiteNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pink noise from the Voss-McCartney algorithm, falling 3dB per octave.
+pub struct PinkNoise {
+    rng: Rng,
+    rows: [f32; Self::ROWS],
+    running_sum: f32,
+    counter: u32,
+}
+
+impl PinkNoise {
+    const ROWS: usize = 16;
+
+    pub fn new() -> Self {
+        Self::with_rng(Rng::from_entropy())
+    }
+
+    /// Makes the noise repeat exactly for a given seed.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self::with_rng(Rng::from_seed(seed))
+    }
+
+    fn with_rng(rng: Rng) -> Self {
+        Self {
+            rng,
+            rows: [0.0; Self::ROWS],
+            running_sum: 0.0,
+            counter: 0,
+        }
+    }
+
+    pub fn get_sample(&mut self) -> f32 {
+        self.counter = self.counter.wrapping_add(1);
+
+        // Each row is refreshed half as often as the one before it.
+        let row = self.counter.trailing_zeros() as usize;
+
+        if row < Self::ROWS {
+            let value = self.rng.next_bipolar();
+            self.running_sum += value - self.rows[row];
+            self.rows[row] = value;
+        }
+
+        let white = self.rng.next_bipolar();
+        (self.running_sum + white) / (Self::ROWS + 1) as f32 * 2.5
+    }
+}
+
+impl Default for PinkNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Brown noise, integrated white noise falling 6dB per octave.
+pub struct BrownNoise {
+    rng: Rng,
+    level: f32,
+}
+
+impl BrownNoise {
+    const LEAK: f32 = 0.02;
+    const GAIN: f32 = 3.5;
+
+    pub fn new() -> Self {
+        Self {
+            rng: Rng::from_entropy(),
+            level: 0.0,
+        }
+    }
+
+    /// Makes the noise repeat exactly for a given seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::from_seed(seed);
+        self
+    }
+
+    pub fn get_sample(&mut self) -> f32 {
+        let white = self.rng.next_bipolar();
+        self.level = (self.level + Self::LEAK * white) / (1.0 + Self::LEAK);
+        self.level * Self::GAIN
+    }
+}
+
+impl Default for BrownNoise {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! impl_noise_node {
+    ($noise:ty) => {
+        impl Node for $noise {
+            fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+                if !inputs.is_empty() {
+                    panic!();
+                }
+
+                for i in 0..Buffer::LEN {
+                    let sample = self.get_sample();
+
+                    for buffer in output.iter_mut() {
+                        buffer[i] = sample;
+                    }
+                }
+            }
+        }
+    };
+}
+
+impl_noise_node!(WhiteNoise);
+impl_noise_node!(PinkNoise);
+impl_noise_node!(BrownNoise);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render<N: Node>(mut node: N, blocks: usize) -> Vec<f32> {
+        let mut output = [Buffer::SILENT];
+        let mut rendered = Vec::new();
+
+        for _ in 0..blocks {
+            node.process(&[], &mut output);
+            rendered.extend_from_slice(&output[0]);
+        }
+
+        rendered
+    }
+
+    #[test]
+    fn seeded_noise_repeats() {
+        assert_eq!(
+            render(WhiteNoise::new().with_seed(7), 8),
+            render(WhiteNoise::new().with_seed(7), 8)
+        );
+        assert_eq!(
+            render(PinkNoise::new().with_seed(7), 8),
+            render(PinkNoise::new().with_seed(7), 8)
+        );
+        assert_eq!(
+            render(BrownNoise::new().with_seed(7), 8),
+            render(BrownNoise::new().with_seed(7), 8)
+        );
+    }
+
+    #[test]
+    fn different_seeds_differ() {
+        assert_ne!(
+            render(WhiteNoise::new().with_seed(7), 1),
+            render(WhiteNoise::new().with_seed(8), 1)
+        );
+    }
+
+    #[test]
+    fn white_noise_stays_in_range() {
+        let rendered = render(WhiteNoise::new().with_seed(0), 64);
+
+        assert!(rendered.iter().all(|sample| (-1.0..1.0).contains(sample)));
+        assert!(rendered.iter().any(|sample| *sample != 0.0));
+    }
+}
@@ -0,0 +1,153 @@
+use dasp_graph::{Buffer, Input, Node};
+
+/// A single cycle of a periodic waveform, evaluated by `Oscillator`.
+pub trait Waveform {
+    /// Number of modulation inputs the waveform reads, following the oscillator's v/oct input.
+    const INPUTS: usize = 0;
+
+    /// Returns the value at `phase`, in `[0, 1)`, given the phase increment per sample `dt`.
+    fn sample(&mut self, phase: f32, dt: f32) -> f32;
+
+    /// Receives the current value of modulation input `index`, before each call to `sample`.
+    fn modulate(&mut self, _index: usize, _value: f32) {}
+}
+
+impl<F: FnMut(f32) -> f32> Waveform for F {
+    fn sample(&mut self, phase: f32, _dt: f32) -> f32 {
+        self(phase)
+    }
+}
+
+/// How the FM input changes an oscillator's frequency.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FmMode {
+    /// Linear FM, with the frequency held at zero rather than going negative.
+    Linear,
+    /// Linear FM that lets the frequency go negative, running the phase backwards.
+    ThroughZero,
+}
+
+/// Phase-accumulating oscillator following the `freq * 2^v_oct` pitch convention.
+///
+/// Inputs are taken in order:
+///
+/// - an optional v/oct input,
+/// - the waveform's own modulation inputs, if it has any,
+/// - a sync input, which resets the phase on each rising edge through zero,
+/// - an FM input, which scales the frequency by `1 + fm_depth * fm`.
+///
+/// To use a later input, every input before it has to be patched. The waveform is written to
+/// every output buffer. Band-limited waveforms are not corrected for sync resets or for negative
+/// frequencies.
+pub struct Oscillator<W: Waveform> {
+    freq: f32,
+    phase: f32,
+    sample_rate: f32,
+    waveform: W,
+    fm_mode: FmMode,
+    fm_depth: f32,
+    last_sync: f32,
+}
+
+impl<W: Waveform> Oscillator<W> {
+    const V_OCT_INDEX: usize = 0;
+    const FIRST_MODULATION_INDEX: usize = 1;
+    const SYNC_INDEX: usize = Self::FIRST_MODULATION_INDEX + W::INPUTS;
+    const FM_INDEX: usize = Self::SYNC_INDEX + 1;
+
+    pub fn with_waveform(waveform: W, freq: f32, sample_rate: u32) -> Self {
+        Self {
+            freq,
+            phase: 0.0,
+            sample_rate: sample_rate as f32,
+            waveform,
+            fm_mode: FmMode::Linear,
+            fm_depth: 1.0,
+            last_sync: 0.0,
+        }
+    }
+
+    pub fn with_fm(mut self, mode: FmMode, depth: f32) -> Self {
+        self.fm_mode = mode;
+        self.fm_depth = depth;
+        self
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    pub fn waveform(&self) -> &W {
+        &self.waveform
+    }
+
+    pub fn waveform_mut(&mut self) -> &mut W {
+        &mut self.waveform
+    }
+
+    pub fn get_sample(&mut self, v_oct: Option<f32>) -> f32 {
+        self.next_sample(v_oct.unwrap_or_default(), None, 0.0)
+    }
+
+    fn next_sample(&mut self, v_oct: f32, sync: Option<f32>, fm: f32) -> f32 {
+        let mut freq = self.freq * 2_f32.powf(v_oct) * (1.0 + self.fm_depth * fm);
+
+        if self.fm_mode == FmMode::Linear {
+            freq = freq.max(0.0);
+        }
+
+        let t = 1.0 / self.sample_rate;
+        let dt = freq * t;
+        self.phase = (self.phase + dt).rem_euclid(1.0);
+
+        if let Some(sync) = sync {
+            if self.last_sync <= 0.0 && sync > 0.0 {
+                // Restart from the point between samples where the sync input crossed zero.
+                let elapsed = sync / (sync - self.last_sync);
+                self.phase = (elapsed * dt).rem_euclid(1.0);
+            }
+
+            self.last_sync = sync;
+        }
+
+        self.waveform.sample(self.phase, dt)
+    }
+}
+
+impl<W: Waveform> Node for Oscillator<W> {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        if inputs.len() > Self::FM_INDEX + 1 {
+            panic!();
+        }
+
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let input_sample =
+            |index: usize, i: usize| inputs.get(index).map(|input: &Input| input.buffers()[0][i]);
+
+        for i in 0..Buffer::LEN {
+            let modulation = inputs
+                .iter()
+                .take(Self::SYNC_INDEX)
+                .skip(Self::FIRST_MODULATION_INDEX);
+
+            for (index, input) in modulation.enumerate() {
+                self.waveform.modulate(index, input.buffers()[0][i]);
+            }
+
+            let v_oct = input_sample(Self::V_OCT_INDEX, i).unwrap_or_default();
+            let sync = input_sample(Self::SYNC_INDEX, i);
+            let fm = input_sample(Self::FM_INDEX, i).unwrap_or_default();
+
+            let sample = self.next_sample(v_oct, sync, fm);
+
+            for buffer in output.iter_mut() {
+                buffer[i] = sample;
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::LevelCommand;
+
+use dasp_graph::{Buffer, Input, Node};
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Range, starting value and smoothing of a parameter that can be changed while the graph runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamSpec {
+    pub min: f32,
+    pub max: f32,
+    /// Value to start at, or `None` to keep the value the node was built with.
+    pub default: Option<f32>,
+    /// Time in seconds to glide most of the way to a new value.
+    pub smoothing: f32,
+}
+
+/// A node whose output level can be handed over to a parameter.
+pub trait ParamTarget {
+    /// Applies `spec` and returns the channel that now controls the level, along with the value
+    /// it starts at.
+    fn attach(&mut self, spec: ParamSpec) -> (Sender<LevelCommand>, f32);
+}
+
+/// A `Level` that is kept within a range and glides to new values instead of jumping.
+///
+/// Smoothing is a one-pole lag, so the smoothing time is the time taken to cover about 63% of a
+/// change. With no smoothing new values are output from the start of the next block.
+pub struct Param {
+    value: f32,
+    target: f32,
+    min: f32,
+    max: f32,
+    smoothing: f32,
+    sample_rate: f32,
+    rx: Option<Receiver<LevelCommand>>,
+}
+
+impl Param {
+    pub fn new(value: f32, sample_rate: u32) -> Self {
+        Self {
+            value,
+            target: value,
+            min: f32::MIN,
+            max: f32::MAX,
+            smoothing: 0.0,
+            sample_rate: sample_rate as f32,
+            rx: None,
+        }
+    }
+
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.min = min;
+        self.max = max;
+        self.target = self.target.clamp(min, max);
+        self.value = self.target;
+        self
+    }
+
+    pub fn with_smoothing(mut self, seconds: f32) -> Self {
+        self.smoothing = seconds.max(0.0);
+        self
+    }
+
+    pub fn with_channel(mut self) -> (Self, Sender<LevelCommand>) {
+        let (tx, rx) = mpsc::channel();
+        self.rx = Some(rx);
+        (self, tx)
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate as f32;
+    }
+
+    fn process_commands(&mut self) {
+        while let Some(command) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            let target = match command {
+                LevelCommand::DeltaLevel(delta) => self.target + delta,
+                LevelCommand::SetLevel(level) => level,
+            };
+
+            self.target = target.clamp(self.min, self.max);
+        }
+    }
+
+    fn coefficient(&self) -> f32 {
+        if self.smoothing > 0.0 {
+            1.0 - (-1.0 / (self.smoothing * self.sample_rate)).exp()
+        } else {
+            1.0
+        }
+    }
+}
+
+impl ParamTarget for Param {
+    fn attach(&mut self, spec: ParamSpec) -> (Sender<LevelCommand>, f32) {
+        let (tx, rx) = mpsc::channel();
+
+        self.min = spec.min;
+        self.max = spec.max;
+        self.smoothing = spec.smoothing.max(0.0);
+        self.target = spec
+            .default
+            .unwrap_or(self.target)
+            .clamp(spec.min, spec.max);
+        self.value = self.target;
+        self.rx = Some(rx);
+
+        (tx, self.target)
+    }
+}
+
+impl Node for Param {
+    fn process(&mut self, _inputs: &[Input], output: &mut [Buffer]) {
+        self.process_commands();
+
+        let coefficient = self.coefficient();
+
+        for i in 0..Buffer::LEN {
+            self.value += (self.target - self.value) * coefficient;
+
+            for buffer in output.iter_mut() {
+                buffer[i] = self.value;
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use super::Clock;
+use crate::util::Rng;
+
+use dasp_graph::{Buffer, Input, Node};
+
+/// Holds a new value each time its clock input rises to `Clock::HIGH`.
+///
+/// Input 0 is the clock. If input 1 is patched its current sample is held, otherwise a random
+/// voltage is picked uniformly from the configured range, which defaults to -1 to 1.
+pub struct SampleAndHold {
+    rng: Rng,
+    min: f32,
+    max: f32,
+    held: f32,
+    clock_high: bool,
+}
+
+impl SampleAndHold {
+    const CLOCK_INDEX: usize = 0;
+    const SIGNAL_INDEX: usize = 1;
+
+    pub fn new() -> Self {
+        Self {
+            rng: Rng::from_entropy(),
+            min: -1.0,
+            max: 1.0,
+            held: 0.0,
+            clock_high: false,
+        }
+    }
+
+    /// Makes the random voltages repeat exactly for a given seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::from_seed(seed);
+        self
+    }
+
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    fn random_voltage(&mut self) -> f32 {
+        let unit = (self.rng.next_bipolar() + 1.0) / 2.0;
+        self.min + unit * (self.max - self.min)
+    }
+}
+
+impl Default for SampleAndHold {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Node for SampleAndHold {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        for input in inputs {
+            if input.buffers().len() != 1 {
+                panic!();
+            }
+        }
+
+        let clock_buf = inputs
+            .get(Self::CLOCK_INDEX)
+            .map(|input| &input.buffers()[0])
+            .unwrap_or(&Buffer::SILENT);
+        let signal_buf = inputs
+            .get(Self::SIGNAL_INDEX)
+            .map(|input| &input.buffers()[0]);
+
+        for i in 0..Buffer::LEN {
+            let clock_high = clock_buf[i] >= Clock::HIGH;
+
+            if clock_high && !self.clock_high {
+                self.held = match signal_buf {
+                    Some(signal_buf) => signal_buf[i],
+                    None => self.random_voltage(),
+                };
+            }
+
+            self.clock_high = clock_high;
+
+            for buffer in output.iter_mut() {
+                buffer[i] = self.held;
+            }
+        }
+    }
+}
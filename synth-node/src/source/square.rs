@@ -1,65 +1,60 @@
-use dasp_graph::{Buffer, Input, Node};
-
-pub struct Square {
-    freq: f32,
-    phase: f32,
-    sample_rate: f32,
+use super::{blep::poly_blep, Oscillator, Waveform};
+
+/// A pulse wave, high for the first `pulse_width` of each cycle.
+///
+/// Its modulation input is added to `pulse_width`, and the sum is clamped to `[0, 1]`.
+pub struct SquareWave {
+    pub band_limited: bool,
+    pub pulse_width: f32,
+    pulse_width_mod: f32,
 }
 
-impl Square {
-    pub fn new(freq: f32, sample_rate: u32) -> Self {
+impl Default for SquareWave {
+    fn default() -> Self {
         Self {
-            freq,
-            phase: 0.0,
-            sample_rate: sample_rate as f32,
+            band_limited: false,
+            pulse_width: 0.5,
+            pulse_width_mod: 0.0,
         }
     }
+}
 
-    pub fn set_sample_rate(&mut self, sample_rate: u32) {
-        self.sample_rate = sample_rate as f32;
-    }
+impl Waveform for SquareWave {
+    const INPUTS: usize = 1;
 
-    pub fn get_sample(&mut self, v_oct: Option<f32>) -> f32 {
-        let freq = self.freq * 2_f32.powf(v_oct.unwrap_or_default());
+    fn sample(&mut self, phase: f32, dt: f32) -> f32 {
+        let pulse_width = (self.pulse_width + self.pulse_width_mod).clamp(0.0, 1.0);
 
-        let t = 1.0 / self.sample_rate;
-        self.phase = (self.phase + freq * t) % 1.0;
+        let sample = if phase < pulse_width { 1.0 } else { -1.0 };
 
-        if self.phase < 0.5 {
-            1.0
+        if self.band_limited {
+            let falling = (phase + 1.0 - pulse_width) % 1.0;
+            sample + 2.0 * poly_blep(phase, dt) - 2.0 * poly_blep(falling, dt)
         } else {
-            -1.0
+            sample
         }
     }
+
+    fn modulate(&mut self, _index: usize, value: f32) {
+        self.pulse_width_mod = value;
+    }
 }
 
-impl Node for Square {
-    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
-        match inputs.len() {
-            0 => {
-                for i in 0..Buffer::LEN {
-                    for buffer in output.iter_mut() {
-                        let sample = self.get_sample(None);
-                        buffer[i] = sample;
-                    }
-                }
-            }
-            1 => {
-                for i in 0..Buffer::LEN {
-                    if inputs[0].buffers().len() != 1 {
-                        panic!();
-                    }
+pub type Square = Oscillator<SquareWave>;
 
-                    let v_oct_buf = &inputs[0].buffers()[0];
+impl Square {
+    pub fn new(freq: f32, sample_rate: u32) -> Self {
+        Self::with_waveform(SquareWave::default(), freq, sample_rate)
+    }
 
-                    for buffer in output.iter_mut() {
-                        let v_oct = v_oct_buf[i];
-                        let sample = self.get_sample(Some(v_oct));
-                        buffer[i] = sample;
-                    }
-                }
-            }
-            _ => panic!(),
-        }
+    pub fn with_pulse_width(mut self, pulse_width: f32) -> Self {
+        self.waveform_mut().pulse_width = pulse_width;
+        self
+    }
+
+    /// Applies PolyBLEP correction to reduce aliasing.
+    pub fn band_limited(mut self) -> Self {
+        self.waveform_mut().band_limited = true;
+        self
     }
 }
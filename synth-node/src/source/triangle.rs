@@ -1,67 +1,40 @@
-use dasp_graph::{Buffer, Input, Node};
+use super::{blep::poly_blamp, Oscillator, Waveform};
 
-pub struct Triangle {
-    freq: f32,
-    phase: f32,
-    sample_rate: f32,
+#[derive(Default)]
+pub struct TriangleWave {
+    pub band_limited: bool,
 }
 
-impl Triangle {
-    pub fn new(freq: f32, sample_rate: u32) -> Self {
-        Self {
-            freq,
-            phase: 0.0,
-            sample_rate: sample_rate as f32,
-        }
-    }
-
-    pub fn set_sample_rate(&mut self, sample_rate: u32) {
-        self.sample_rate = sample_rate as f32;
-    }
-
-    pub fn get_sample(&mut self, v_oct: Option<f32>) -> f32 {
-        let freq = self.freq * 2_f32.powf(v_oct.unwrap_or_default());
-
-        let t = 1.0 / self.sample_rate;
-        self.phase = (self.phase + freq * t) % 1.0;
+impl Waveform for TriangleWave {
+    fn sample(&mut self, phase: f32, dt: f32) -> f32 {
+        let sample = if phase < 0.25 {
+            phase * 4.0
+        } else if phase < 0.75 {
+            2.0 - (phase * 4.0)
+        } else {
+            phase * 4.0 - 4.0
+        };
 
-        if self.phase < 0.25 {
-            self.phase * 4.0
-        } else if self.phase < 0.75 {
-            2.0 - (self.phase * 4.0)
+        if self.band_limited {
+            let slope_change = 8.0 * dt;
+            sample - slope_change * poly_blamp((phase + 0.75) % 1.0, dt)
+                + slope_change * poly_blamp((phase + 0.25) % 1.0, dt)
         } else {
-            self.phase * 4.0 - 4.0
+            sample
         }
     }
 }
 
-impl Node for Triangle {
-    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
-        match inputs.len() {
-            0 => {
-                for i in 0..Buffer::LEN {
-                    for buffer in output.iter_mut() {
-                        let sample = self.get_sample(None);
-                        buffer[i] = sample;
-                    }
-                }
-            }
-            1 => {
-                for i in 0..Buffer::LEN {
-                    if inputs[0].buffers().len() != 1 {
-                        panic!();
-                    }
+pub type Triangle = Oscillator<TriangleWave>;
 
-                    let v_oct_buf = &inputs[0].buffers()[0];
+impl Triangle {
+    pub fn new(freq: f32, sample_rate: u32) -> Self {
+        Self::with_waveform(TriangleWave::default(), freq, sample_rate)
+    }
 
-                    for buffer in output.iter_mut() {
-                        let v_oct = v_oct_buf[i];
-                        let sample = self.get_sample(Some(v_oct));
-                        buffer[i] = sample;
-                    }
-                }
-            }
-            _ => panic!(),
-        }
+    /// Applies PolyBLAMP correction to reduce aliasing.
+    pub fn band_limited(mut self) -> Self {
+        self.waveform_mut().band_limited = true;
+        self
     }
 }
@@ -1,3 +1,11 @@
 mod pass_or_default;
+#[cfg(test)]
+mod patch;
+mod rng;
+mod select;
 
 pub use pass_or_default::PassOrDefault;
+#[cfg(test)]
+pub(crate) use patch::process_patched;
+pub(crate) use rng::Rng;
+pub use select::Select;
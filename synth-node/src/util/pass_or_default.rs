@@ -1,5 +1,9 @@
+use crate::source::{LevelCommand, ParamSpec, ParamTarget};
+
 use dasp_graph::{node::Pass, Buffer, Input, Node};
 
+use std::sync::mpsc::Sender;
+
 pub struct PassOrDefault<T: Node> {
     pass: Pass,
     default: T,
@@ -23,3 +27,9 @@ impl<T: Node> Node for PassOrDefault<T> {
         }
     }
 }
+
+impl<T: Node + ParamTarget> ParamTarget for PassOrDefault<T> {
+    fn attach(&mut self, spec: ParamSpec) -> (Sender<LevelCommand>, f32) {
+        self.default.attach(spec)
+    }
+}
@@ -0,0 +1,41 @@
+use dasp_graph::{Buffer, Input, Node, NodeData, Processor};
+use petgraph::graph::DiGraph;
+
+/// The node under test, or a fixed buffer standing in for whatever is patched into it.
+enum Patched<'a, N> {
+    Node(&'a mut N),
+    Fixed(Box<Buffer>),
+}
+
+impl<N: Node> Node for Patched<'_, N> {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        match self {
+            Self::Node(node) => node.process(inputs, output),
+            Self::Fixed(buffer) => output[0] = (**buffer).clone(),
+        }
+    }
+}
+
+/// Processes one block of `node` with `inputs` patched into it in order, returning its
+/// `outputs` buffers.
+pub(crate) fn process_patched<N: Node>(
+    node: &mut N,
+    inputs: &[Buffer],
+    outputs: usize,
+) -> Vec<Buffer> {
+    let mut graph = DiGraph::<NodeData<Patched<N>>, ()>::new();
+    let dst = graph.add_node(NodeData::new(
+        Patched::Node(node),
+        vec![Buffer::SILENT; outputs],
+    ));
+
+    // Inputs come out of the graph in the reverse of the order their edges were added.
+    for input in inputs.iter().rev() {
+        let src = graph.add_node(NodeData::new1(Patched::Fixed(Box::new(input.clone()))));
+        graph.add_edge(src, dst, ());
+    }
+
+    Processor::with_capacity(inputs.len() + 1).process(&mut graph, dst);
+
+    graph.remove_node(dst).unwrap().buffers
+}
@@ -0,0 +1,28 @@
+use dasp_graph::{Buffer, Input, Node};
+
+/// Copies one buffer of its first input to every output buffer.
+///
+/// Picks a single channel out of a node that writes several, such as the ratio outputs of a
+/// `Clock`. Outputs silence if the input has no buffer at `index`.
+pub struct Select {
+    index: usize,
+}
+
+impl Select {
+    pub fn new(index: usize) -> Self {
+        Self { index }
+    }
+}
+
+impl Node for Select {
+    fn process(&mut self, inputs: &[Input], output: &mut [Buffer]) {
+        let selected = inputs
+            .first()
+            .and_then(|input| input.buffers().get(self.index))
+            .unwrap_or(&Buffer::SILENT);
+
+        for buffer in output.iter_mut() {
+            buffer.copy_from_slice(selected);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use super::VoiceMeter;
+use crate::{midi::note_to_v_oct, source::Clock};
+
+use dasp_graph::{Buffer, Input, Node};
+
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    mpsc::{self, Receiver, Sender},
+    Arc,
+};
+
+pub enum NoteEvent {
+    /// A MIDI note number with a velocity between 0 and 1.
+    NoteOn {
+        note: u8,
+        velocity: f32,
+    },
+    NoteOff {
+        note: u8,
+    },
+    AllNotesOff,
+}
+
+/// Which sounding voice gives way when a note arrives and every voice is held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StealPolicy {
+    /// The voice whose note started first.
+    Oldest,
+    /// The voice with the lowest peak level over the last block, as seen by its `VoiceMeter`.
+    Quietest,
+    /// The voice playing the lowest note.
+    Lowest,
+}
+
+#[derive(Clone, Debug, Default)]
+struct VoiceState {
+    note: Option<u8>,
+    held: bool,
+    v_oct: f32,
+    velocity: f32,
+    started: u64,
+    released: u64,
+    retrigger: bool,
+    gate_high: bool,
+}
+
+/// Spreads note events from a channel across a fixed number of voices.
+///
+/// Each voice gets three output buffers, laid out in order from voice 0: the pitch in volts per
+/// octave relative to the reference note, a gate at `Clock::HIGH` while the note is held, and the
+/// velocity. New notes go to the voice that has been released the longest, and only steal a held
+/// voice once none are free. A voice whose gate was still high drops it for the first sample of
+/// the block, so envelopes retrigger.
+pub struct VoiceAllocator {
+    voices: Vec<VoiceState>,
+    levels: Vec<Arc<AtomicU32>>,
+    policy: StealPolicy,
+    reference_note: u8,
+    events: u64,
+    rx: Option<Receiver<NoteEvent>>,
+}
+
+impl VoiceAllocator {
+    pub const BUFFERS_PER_VOICE: usize = 3;
+    pub const PITCH_BUFFER: usize = 0;
+    pub const GATE_BUFFER: usize = 1;
+    pub const VELOCITY_BUFFER: usize = 2;
+
+    pub fn new(voices: usize, policy: StealPolicy) -> Self {
+        Self {
+            voices: vec![VoiceState::default(); voices],
+            levels: (0..voices).map(|_| Arc::new(AtomicU32::new(0))).collect(),
+            policy,
+            reference_note: 60,
+            events: 0,
+            rx: None,
+        }
+    }
+
+    /// Sets the MIDI note that is played at 0V, which defaults to middle C.
+    pub fn with_reference_note(mut self, note: u8) -> Self {
+        self.reference_note = note;
+        self
+    }
+
+    pub fn with_channel(mut self) -> (Self, Sender<NoteEvent>) {
+        let (tx, rx) = mpsc::channel();
+        self.rx = Some(rx);
+        (self, tx)
+    }
+
+    pub fn voices(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Makes a meter to patch after the output of `voice`, which the quietest stealing policy
+    /// relies on.
+    pub fn meter(&self, voice: usize) -> VoiceMeter {
+        VoiceMeter::new(self.levels[voice].clone())
+    }
+
+    /// Index of the buffer carrying `buffer` of `voice`.
+    pub fn buffer_index(voice: usize, buffer: usize) -> usize {
+        voice * Self::BUFFERS_PER_VOICE + buffer
+    }
+
+    pub fn note_on(&mut self, note: u8, velocity: f32) {
+        if self.voices.is_empty() {
+            return;
+        }
+
+        self.events += 1;
+
+        let voice = self
+            .voices
+            .iter()
+            .position(|voice| voice.note == Some(note))
+            .or_else(|| self.free_voice())
+            .unwrap_or_else(|| self.stolen_voice());
+
+        let v_oct = note_to_v_oct(note, self.reference_note);
+        let state = &mut self.voices[voice];
+
+        state.retrigger = state.gate_high;
+        state.note = Some(note);
+        state.held = true;
+        state.v_oct = v_oct;
+        state.velocity = velocity;
+        state.started = self.events;
+    }
+
+    pub fn note_off(&mut self, note: u8) {
+        self.events += 1;
+
+        for voice in self.voices.iter_mut() {
+            if voice.held && voice.note == Some(note) {
+                voice.held = false;
+                voice.released = self.events;
+            }
+        }
+    }
+
+    pub fn all_notes_off(&mut self) {
+        for note in 0..=127 {
+            self.note_off(note);
+        }
+    }
+
+    fn free_voice(&self) -> Option<usize> {
+        self.voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| !voice.held)
+            .min_by_key(|(_, voice)| voice.released)
+            .map(|(index, _)| index)
+    }
+
+    fn stolen_voice(&self) -> usize {
+        let voices = self.voices.iter().enumerate();
+
+        let stolen = match self.policy {
+            StealPolicy::Oldest => voices.min_by_key(|(_, voice)| voice.started),
+            StealPolicy::Quietest => {
+                voices.min_by(|(a, _), (b, _)| self.level(*a).total_cmp(&self.level(*b)))
+            }
+            StealPolicy::Lowest => voices.min_by_key(|(_, voice)| voice.note),
+        };
+
+        stolen.map(|(index, _)| index).unwrap_or_default()
+    }
+
+    fn level(&self, voice: usize) -> f32 {
+        f32::from_bits(self.levels[voice].load(Ordering::Relaxed))
+    }
+
+    fn process_events(&mut self) {
+        while let Some(event) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            match event {
+                NoteEvent::NoteOn { note, velocity } => self.note_on(note, velocity),
+                NoteEvent::NoteOff { note } => self.note_off(note),
+                NoteEvent::AllNotesOff => self.all_notes_off(),
+            }
+        }
+    }
+}
+
+impl Node for VoiceAllocator {
+    fn process(&mut self, _inputs: &[Input], output: &mut [Buffer]) {
+        self.process_events();
+
+        for (index, buffer) in output.iter_mut().enumerate() {
+            let voice = match self.voices.get(index / Self::BUFFERS_PER_VOICE) {
+                Some(voice) => voice,
+                None => {
+                    *buffer = Buffer::SILENT;
+                    continue;
+                }
+            };
+
+            match index % Self::BUFFERS_PER_VOICE {
+                Self::PITCH_BUFFER => buffer.iter_mut().for_each(|s| *s = voice.v_oct),
+                Self::GATE_BUFFER => {
+                    let gate = if voice.held { Clock::HIGH } else { Clock::LOW };
+                    buffer.iter_mut().for_each(|s| *s = gate);
+
+                    if voice.retrigger {
+                        buffer[0] = Clock::LOW;
+                    }
+                }
+                _ => buffer.iter_mut().for_each(|s| *s = voice.velocity),
+            }
+        }
+
+        for voice in self.voices.iter_mut() {
+            voice.retrigger = false;
+            voice.gate_high = voice.held;
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod allocator;
+mod meter;
+
+pub use allocator::{NoteEvent, StealPolicy, VoiceAllocator};
+pub use meter::VoiceMeter;
@@ -1,63 +1,99 @@
-use synth_module::{oscillator::DeriveOscillator, sequencer::StepSequencer, SynthModule};
+use synth_module::{
+    amplifier::VcaModule, envelope::AdsrEnvelope, filter::SvfModule, oscillator::DeriveOscillator,
+    render::Renderer, sequencer::GateSequencer, voice::SubtractiveVoice, Graph, SynthModule,
+};
 use synth_node::{
-    sink::CpalMonoSink,
-    source::{Clock, Level},
+    ops::Response,
+    sequencer::Step,
+    sink::{CpalSink, WavFormat, WavSink},
+    source::Clock,
 };
 
 use cpal::traits::{DeviceTrait, HostTrait};
-use dasp_graph::{BoxedNode, NodeData, Processor};
-use petgraph::Directed;
-
-type Graph = petgraph::Graph<NodeData<BoxedNode>, (), Directed, u32>;
-
-fn main() -> Result<(), anyhow::Error> {
-    let host = cpal::default_host();
-    let device = host
-        .default_output_device()
-        .expect("failed to find a default output device");
-    let config = device.default_output_config()?;
+use dasp_graph::{NodeData, Processor};
+use petgraph::graph::NodeIndex;
 
-    let mut g = Graph::new();
-    let mut p = Processor::with_capacity(1024);
+const RENDER_PATH: &str = "synth.wav";
+const RENDER_SAMPLE_RATE: u32 = 44_100;
+const RENDER_SECONDS: u32 = 10;
 
-    let clock = Clock::new(160.0, config.sample_rate().0);
+fn build_patch(g: &mut Graph, sample_rate: u32) -> NodeIndex<u32> {
+    let clock = Clock::new(160.0, sample_rate);
     let clock_idx = g.add_node(NodeData::boxed1(clock));
 
-    let sequencer = StepSequencer::<4>::new([
-        Level::new(0.0),
-        Level::new(1.0),
-        Level::new(0.25),
-        Level::new(0.5),
+    let sequencer = GateSequencer::<4>::new([
+        Step::new(0.0),
+        Step::new(1.0),
+        Step::new(0.25),
+        Step::new(0.5),
     ])
-    .build_graph(&mut g);
+    .build_graph(g);
     g.add_edge(clock_idx, sequencer.clock_in().unwrap(), ());
 
-    let oscillator = DeriveOscillator::new(130.0, config.sample_rate().0).build_graph(&mut g);
+    let voice = SubtractiveVoice::new(
+        DeriveOscillator::band_limited(130.0, sample_rate),
+        SvfModule::new(1200.0, 0.3, sample_rate),
+        AdsrEnvelope::new(0.005, 0.1, 0.6, 0.15, sample_rate),
+        VcaModule::new(Response::Linear, 0.0),
+        sample_rate,
+    )
+    .build_graph(g);
     g.add_edge(
         sequencer.v_oct_out().unwrap(),
-        oscillator.v_oct_in().unwrap(),
+        voice.v_oct_in().unwrap(),
         (),
     );
+    g.add_edge(sequencer.gate_out().unwrap(), voice.gate_in().unwrap(), ());
 
-    let sink = match config.sample_format() {
-        cpal::SampleFormat::F32 => {
-            let sink = CpalMonoSink::<f32>::new(&device, &config.into());
-            BoxedNode::new(sink)
-        }
-        cpal::SampleFormat::I16 => {
-            let sink = CpalMonoSink::<i16>::new(&device, &config.into());
-            BoxedNode::new(sink)
-        }
-        cpal::SampleFormat::U16 => {
-            let sink = CpalMonoSink::<u16>::new(&device, &config.into());
-            BoxedNode::new(sink)
+    voice.audio_out().unwrap()
+}
+
+fn render(mut g: Graph) -> Result<(), anyhow::Error> {
+    let out_idx = build_patch(&mut g, RENDER_SAMPLE_RATE);
+
+    let frames = (RENDER_SAMPLE_RATE * RENDER_SECONDS) as usize;
+    let sink = WavSink::create(RENDER_PATH, RENDER_SAMPLE_RATE, 1, WavFormat::Int16)?
+        .with_duration(frames);
+
+    let sink_idx = g.add_node(NodeData::boxed1(sink));
+    g.add_edge(out_idx, sink_idx, ());
+
+    Renderer::new(&mut g, sink_idx).run(Renderer::blocks_for(frames));
+
+    Ok(())
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let host = cpal::default_host();
+
+    let mut g = Graph::new();
+    let mut p = Processor::with_capacity(1024);
+
+    let output = host.default_output_device().and_then(|device| {
+        let config = device.default_output_config().ok()?;
+        Some((device, config))
+    });
+
+    let (device, config) = match output {
+        Some(output) => output,
+        None => {
+            eprintln!("no output device available, rendering to {}", RENDER_PATH);
+            return render(g);
         }
     };
 
+    let out_idx = build_patch(&mut g, config.sample_rate().0);
+
+    let (sink, monitor) = CpalSink::new(&device, &config)?;
+
     let sink_idx = g.add_node(NodeData::boxed1(sink));
-    g.add_edge(oscillator.sine_out().unwrap(), sink_idx, ());
+    g.add_edge(out_idx, sink_idx, ());
 
     loop {
         p.process(&mut g, sink_idx);
+
+        if let Some(e) = monitor.try_recv_error() {
+            return Err(e.into());
+        }
     }
 }